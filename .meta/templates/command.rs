@@ -1,3 +1,11 @@
+{{#if description}}
+//! {{description}}
+{{/if}}
+{{#unless register}}
+// Not added to main.rs yet, remove this once it is
+#![allow(dead_code)]
+{{/unless}}
+
 #[derive(clap::Args)]
 pub(crate) struct Arguments {
     #[clap(subcommand)]
@@ -7,24 +15,24 @@ pub(crate) struct Arguments {
 
 #[derive(clap::Subcommand)]
 pub(crate) enum Commands {
-    Example {
+    {{Name}} {
         arg1: Option<String>,
         arg2: Option<String>,
     },
-    ExampleNoArgs,
+    {{Name}}NoArgs,
 }
 
 pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
     match &args.command {
         Some(commands) => match commands {
-            Commands::Example { arg1, arg2 } => example_fn(arg1, arg2),
-            Commands::ExampleNoArgs => example_fn(&None, &None),
+            Commands::{{Name}} { arg1, arg2 } => {{name}}_fn(arg1, arg2),
+            Commands::{{Name}}NoArgs => {{name}}_fn(&None, &None),
         },
-        None => example_fn(&None, &None),
+        None => {{name}}_fn(&None, &None),
     }
 }
 
-fn example_fn(_arg1: &Option<String>, _arg2: &Option<String>) -> crate::Result<()> {
-    println!("This is an example function");
+fn {{name}}_fn(_arg1: &Option<String>, _arg2: &Option<String>) -> crate::Result<()> {
+    println!("This is the {{name}} command");
     Ok(())
 }
@@ -0,0 +1,27 @@
+{{#if description}}
+//! {{description}}
+{{/if}}
+{{#unless register}}
+// Not added to main.rs yet, remove this once it is
+#![allow(dead_code)]
+{{/unless}}
+mod run;
+
+#[derive(clap::Args)]
+pub(crate) struct Arguments {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(clap::Subcommand)]
+pub(crate) enum Commands {
+    /// Run {{name}}
+    Run { arg: Option<String> },
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    match &args.command {
+        Some(Commands::Run { arg }) => run::run(arg.as_deref()),
+        None => run::run(None),
+    }
+}
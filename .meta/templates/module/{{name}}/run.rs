@@ -0,0 +1,7 @@
+/// Prefix for everything {{name}} prints
+const {{NAME}}_PREFIX: &str = "[{{name}}]";
+
+pub(super) fn run(arg: Option<&str>) -> crate::Result<()> {
+    println!("{} running with {arg:?}", {{NAME}}_PREFIX);
+    Ok(())
+}
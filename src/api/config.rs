@@ -0,0 +1,65 @@
+//! User configuration, read once at startup from
+//! `$XDG_CONFIG_HOME/utils/config.toml` (or `~/.config/utils/config.toml`).
+//! `UTILS_CONFIG` points at a different file.
+use std::sync::OnceLock;
+
+use crate::api::terminal::{Keymap, KeymapConfig, Theme, ThemeConfig};
+
+const CONFIG_ENV: &str = "UTILS_CONFIG";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) keymap: KeymapConfig,
+    pub(crate) theme: ThemeConfig,
+    /// Let the widgets be clicked and scrolled. Off by default because
+    /// capturing the mouse stops the terminal's own text selection.
+    pub(crate) mouse: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ConfigErrors {
+    #[error("Failed to read config file {0}")]
+    Read(String, #[source] std::io::Error),
+    #[error("Invalid config file {0}")]
+    Parse(String, #[source] toml::de::Error),
+}
+
+/// Load the config file, if there is one. Call once before using [`get`].
+pub(crate) fn init() -> crate::Result<()> {
+    let config = match path() {
+        Some(path) if path.exists() => {
+            let display = path.display().to_string();
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| ConfigErrors::Read(display.clone(), e))?;
+            let config: Config =
+                toml::from_str(&contents).map_err(|e| ConfigErrors::Parse(display, e))?;
+            Keymap::validate(&config.keymap)?;
+            Theme::validate(&config.theme)?;
+            config
+        }
+        _ => Config::default(),
+    };
+
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// The loaded config, or the defaults if [`init`] hasn't run (e.g. in tests).
+pub(crate) fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+fn path() -> Option<std::path::PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        return Some(path.into());
+    }
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| std::path::Path::new(&h).join(".config")))?;
+
+    Some(config_dir.join("utils").join("config.toml"))
+}
@@ -1 +1,2 @@
+pub(crate) mod config;
 pub(crate) mod terminal;
@@ -0,0 +1,130 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use crossterm::event::Event;
+use ratatui::Frame;
+
+use super::{InlineTerminal, TerminalBackend};
+
+/// How long to wait for input when nothing else can wake the loop
+const IDLE_POLL: Duration = Duration::from_millis(100);
+/// How long to wait for input while messages may still arrive, short enough
+/// that they show up without a noticeable lag
+const FEED_POLL: Duration = Duration::from_millis(30);
+
+/// Something for an [`App`] to react to.
+pub(crate) enum Message<M> {
+    /// Keys, mouse clicks and resizes from the terminal
+    Input(Event),
+    /// Sent from elsewhere in the program over the loop's channel
+    External(M),
+}
+
+/// A widget split Elm-style into updating its state and rendering it, so
+/// the same loop can drive it from terminal input and from other threads.
+pub(crate) trait App {
+    /// What other threads can send the widget while it's on screen
+    type Message;
+    /// What the widget returns once it's done
+    type Output;
+
+    /// Apply `message`, returning the output when it finishes the widget.
+    fn update(&mut self, message: Message<Self::Message>) -> crate::Result<Option<Self::Output>>;
+
+    /// Draw the current state.
+    fn view(&mut self, frame: &mut Frame);
+}
+
+/// Run `app` until it finishes, applying input from `terminal` and anything
+/// sent on `messages`, and redrawing only after something changed.
+///
+/// Messages keep being read until every sender has been dropped.
+pub(crate) fn run<A: App, B: TerminalBackend>(
+    app: &mut A,
+    terminal: &mut InlineTerminal<B>,
+    messages: Option<&Receiver<A::Message>>,
+) -> crate::Result<A::Output> {
+    let mut messages = messages;
+    let mut redraw = true;
+
+    loop {
+        while let Some(rx) = messages {
+            match rx.try_recv() {
+                Ok(message) => {
+                    redraw = true;
+                    if let Some(output) = app.update(Message::External(message))? {
+                        return Ok(output);
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => messages = None,
+            }
+        }
+
+        if redraw {
+            terminal.draw(|f| app.view(f))?;
+            redraw = false;
+        }
+
+        let timeout = if messages.is_some() {
+            FEED_POLL
+        } else {
+            IDLE_POLL
+        };
+        if let Some(event) = terminal.next_event(timeout)? {
+            redraw = true;
+            if let Some(output) = app.update(Message::Input(event))? {
+                return Ok(output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crossterm::event::KeyCode;
+    use ratatui::widgets::Paragraph;
+
+    use super::*;
+    use crate::api::terminal::ScriptedEvents;
+
+    /// Adds up numbers sent to it, finishing on Enter.
+    struct Sum(u32);
+
+    impl App for Sum {
+        type Message = u32;
+        type Output = u32;
+
+        fn update(&mut self, message: Message<u32>) -> crate::Result<Option<u32>> {
+            Ok(match message {
+                Message::External(n) => {
+                    self.0 += n;
+                    None
+                }
+                Message::Input(Event::Key(key)) if key.code == KeyCode::Enter => Some(self.0),
+                Message::Input(_) => None,
+            })
+        }
+
+        fn view(&mut self, frame: &mut Frame) {
+            frame.render_widget(Paragraph::new(format!("sum {}", self.0)), frame.area());
+        }
+    }
+
+    #[test]
+    fn applies_messages_before_input() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        drop(tx);
+
+        let events = ScriptedEvents::keys([KeyCode::Char('x'), KeyCode::Enter]);
+        let mut terminal = InlineTerminal::headless(20, 1, events);
+        let result = run(&mut Sum(0), &mut terminal, Some(&rx)).unwrap();
+
+        assert_eq!(result, 3);
+        assert_eq!(terminal.rendered()[0], "sum 3");
+    }
+}
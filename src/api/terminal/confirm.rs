@@ -1,16 +1,42 @@
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
-use ratatui::{prelude::*, widgets::Paragraph};
+use std::convert::Infallible;
 
-use super::InlineTerminal;
+use crossterm::event::{Event, KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::{
+    prelude::*,
+    widgets::{Paragraph, Wrap},
+};
+
+use super::app::{self, App, Message};
+use super::{
+    Action, InlineTerminal, Keymap, TerminalBackend, TerminalErrors, Theme, is_interactive,
+    is_interrupt, wrapped_height,
+};
+
+/// One line of a [`ConfirmPrompt::changes`] summary.
+pub(crate) enum Change {
+    Added(String),
+    Removed(String),
+}
 
 /// A reusable Yes/No confirmation prompt rendered inline.
 ///
-/// Displays a header, body lines, and a left/right Yes/No selector.
-/// Returns `true` if confirmed, `false` if cancelled.
-#[allow(dead_code)]
+/// Displays a header, a body that wraps and scrolls when it doesn't fit, and
+/// a left/right Yes/No selector. Returns `true` if confirmed, `false` if
+/// cancelled.
 pub(crate) struct ConfirmPrompt {
     header: String,
     lines: Vec<Line<'static>>,
+    keymap: Keymap,
+    theme: Theme,
+    /// Whether the Yes button is focused, No being the safe default
+    selected_yes: bool,
+    /// How many wrapped rows of the body are scrolled past
+    scroll: u16,
+    /// The furthest the body can scroll, as of the last draw
+    max_scroll: u16,
+    /// Where the buttons were drawn, for mouse clicks
+    yes_area: Rect,
+    no_area: Rect,
 }
 
 impl ConfirmPrompt {
@@ -18,77 +44,379 @@ impl ConfirmPrompt {
         Self {
             header: header.into(),
             lines,
+            keymap: Keymap::current(),
+            theme: Theme::current(),
+            selected_yes: false,
+            scroll: 0,
+            max_scroll: 0,
+            yes_area: Rect::default(),
+            no_area: Rect::default(),
         }
     }
 
-    /// Run the interactive confirmation loop.
-    pub(crate) fn run(&mut self, terminal: &mut InlineTerminal) -> crate::Result<bool> {
-        // Default to "No" (safe choice)
-        let mut selected_yes = false;
+    /// A prompt summing up `changes`: how many lines are added and removed,
+    /// then each of them marked with `+` or `-`, after any `context` lines.
+    pub(crate) fn changes(
+        header: impl Into<String>,
+        context: Vec<Line<'static>>,
+        changes: &[Change],
+    ) -> Self {
+        let theme = Theme::current();
+        let added = changes
+            .iter()
+            .filter(|c| matches!(c, Change::Added(_)))
+            .count();
+        let removed = changes.len() - added;
+
+        let mut lines = context;
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {added} added"), theme.checked),
+            Span::styled(", ", theme.muted),
+            Span::styled(format!("{removed} removed"), theme.error),
+        ]));
+        for change in changes {
+            lines.push(match change {
+                Change::Added(text) => Line::styled(format!("  + {text}"), theme.checked),
+                Change::Removed(text) => Line::styled(format!("  - {text}"), theme.error),
+            });
+        }
+
+        Self::new(header, lines)
+    }
+
+    /// How tall to make the prompt to fit the body, up to `max` rows.
+    pub(crate) fn height(&self, max: u16) -> u16 {
+        (self.lines.len() as u16).saturating_add(5).min(max)
+    }
+
+    /// Run the prompt in an inline terminal of the given size, or as a plain
+    /// `[y/N]` question on stdin/stderr when stdout isn't a terminal.
+    pub(crate) fn prompt(&mut self, height: u16, width: u16) -> crate::Result<bool> {
+        if !is_interactive() {
+            return self.run_plain(&mut std::io::stdin().lock(), &mut std::io::stderr());
+        }
+
+        let mut terminal = InlineTerminal::new(height, width)?;
+        let result = self.run(&mut terminal)?;
+        terminal.cleanup()?;
+        Ok(result)
+    }
+
+    /// Run the non-interactive fallback, reading a yes/no answer from `input`.
+    pub(crate) fn run_plain(
+        &mut self,
+        input: &mut impl std::io::BufRead,
+        output: &mut impl std::io::Write,
+    ) -> crate::Result<bool> {
+        writeln!(output, "{}", self.header)?;
+        for line in &self.lines {
+            writeln!(output, "{line}")?;
+        }
 
         loop {
-            let header = &self.header;
-            let body_lines = &self.lines;
-
-            terminal.draw(|f| {
-                let mut lines: Vec<Line> = Vec::new();
-
-                // Header
-                lines.push(Line::from(vec![Span::styled(
-                    header.as_str(),
-                    Style::default().fg(Color::Yellow).bold(),
-                )]));
-                lines.push(Line::from(""));
-
-                // Body content
-                lines.extend(body_lines.iter().cloned());
-
-                // Confirmation selector
-                lines.push(Line::from(""));
-
-                let yes_style = if selected_yes {
-                    Style::default().bg(Color::DarkGray).fg(Color::Green).bold()
-                } else {
-                    Style::default().fg(Color::DarkGray)
-                };
-                let no_style = if !selected_yes {
-                    Style::default().bg(Color::DarkGray).fg(Color::Red).bold()
-                } else {
-                    Style::default().fg(Color::DarkGray)
-                };
-
-                lines.push(Line::from(vec![
-                    Span::raw("  "),
-                    Span::styled(" Yes, confirm ", yes_style),
-                    Span::raw("  "),
-                    Span::styled(" No, cancel ", no_style),
-                    Span::styled(
-                        "  (←→ select, Enter confirm)",
-                        Style::default().fg(Color::DarkGray),
-                    ),
-                ]));
-
-                f.render_widget(Paragraph::new(lines), f.area());
-            })?;
-
-            if event::poll(std::time::Duration::from_millis(100))? {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind != KeyEventKind::Press {
-                        continue;
-                    }
+            write!(output, "Confirm? [y/N] ")?;
+            output.flush()?;
 
-                    match key.code {
-                        KeyCode::Left | KeyCode::Char('h') => selected_yes = true,
-                        KeyCode::Right | KeyCode::Char('l') => selected_yes = false,
-                        KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
-                        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                            return Ok(false)
-                        }
-                        KeyCode::Enter => return Ok(selected_yes),
-                        _ => {}
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                writeln!(output)?;
+                return Err(TerminalErrors::NoInput.into());
+            }
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "" | "n" | "no" => return Ok(false),
+                _ => writeln!(output, "Please answer y or n")?,
+            }
+        }
+    }
+
+    /// Run the interactive confirmation loop.
+    pub(crate) fn run<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut InlineTerminal<B>,
+    ) -> crate::Result<bool> {
+        app::run(self, terminal, None)
+    }
+
+    fn hint(&self) -> String {
+        let mut entries: Vec<(&[Action], &str)> =
+            vec![(&[Action::FocusConfirm, Action::FocusCancel], "select")];
+        if self.max_scroll > 0 {
+            entries.push((&[Action::Up, Action::Down], "scroll"));
+        }
+        entries.push((&[Action::Confirm], "confirm"));
+        self.keymap.hint(&entries)
+    }
+
+    fn handle_key(&mut self, action: Action) -> Option<bool> {
+        match action {
+            Action::FocusConfirm => self.selected_yes = true,
+            Action::FocusCancel => self.selected_yes = false,
+            Action::SwitchButton => self.selected_yes = !self.selected_yes,
+            Action::Up => self.scroll = self.scroll.saturating_sub(1),
+            Action::Down => self.scroll = (self.scroll + 1).min(self.max_scroll),
+            Action::Top => self.scroll = 0,
+            Action::Bottom => self.scroll = self.max_scroll,
+            Action::Yes => return Some(true),
+            Action::No | Action::Cancel => return Some(false),
+            Action::Confirm => return Some(self.selected_yes),
+            _ => {}
+        }
+        None
+    }
+}
+
+impl App for ConfirmPrompt {
+    type Message = Infallible;
+    type Output = bool;
+
+    fn update(&mut self, message: Message<Infallible>) -> crate::Result<Option<bool>> {
+        let Message::Input(event) = message;
+        Ok(match event {
+            Event::Mouse(mouse) => {
+                let position = Position::new(mouse.column, mouse.row);
+                match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) if self.yes_area.contains(position) => {
+                        Some(true)
                     }
+                    MouseEventKind::Down(MouseButton::Left) if self.no_area.contains(position) => {
+                        Some(false)
+                    }
+                    MouseEventKind::ScrollUp => self.handle_key(Action::Up),
+                    MouseEventKind::ScrollDown => self.handle_key(Action::Down),
+                    _ => None,
                 }
             }
+            Event::Key(key) if key.kind == KeyEventKind::Press && is_interrupt(&key) => Some(false),
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                let actions = [
+                    Action::FocusConfirm,
+                    Action::FocusCancel,
+                    Action::SwitchButton,
+                    Action::Yes,
+                    Action::No,
+                    Action::Up,
+                    Action::Down,
+                    Action::Top,
+                    Action::Bottom,
+                    Action::Cancel,
+                    Action::Confirm,
+                ];
+                self.keymap
+                    .action(&key, &actions)
+                    .and_then(|action| self.handle_key(action))
+            }
+            _ => None,
+        })
+    }
+
+    fn view(&mut self, f: &mut Frame) {
+        let area = f.area();
+        let body = Paragraph::new(self.lines.clone()).wrap(Wrap { trim: false });
+
+        // The body takes the rows it needs, and scrolls when the header,
+        // buttons and spacing leave fewer than that
+        let total = wrapped_height(&Text::from(self.lines.clone()), area.width) as u16;
+        let room = area.height.saturating_sub(4);
+        let height = total.min(room);
+        self.max_scroll = total - height;
+        self.scroll = self.scroll.min(self.max_scroll);
+
+        let [header_area, _, body_area, more_area, buttons_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(height),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        f.render_widget(
+            Paragraph::new(Span::styled(self.header.as_str(), self.theme.header)),
+            header_area,
+        );
+        f.render_widget(body.scroll((self.scroll, 0)), body_area);
+
+        if self.max_scroll > 0 {
+            let shown = format!(
+                "  lines {}-{} of {total}",
+                self.scroll + 1,
+                self.scroll + height
+            );
+            f.render_widget(
+                Paragraph::new(Span::styled(shown, self.theme.muted)),
+                more_area,
+            );
         }
+
+        let (yes_style, no_style) = if self.selected_yes {
+            (self.theme.confirm, self.theme.muted)
+        } else {
+            (self.theme.muted, self.theme.cancel)
+        };
+        let yes = Span::styled(" Yes, confirm ", yes_style);
+        let no = Span::styled(" No, cancel ", no_style);
+        self.yes_area = Rect::new(buttons_area.x + 2, buttons_area.y, yes.width() as u16, 1);
+        self.no_area = Rect::new(
+            self.yes_area.right() + 2,
+            buttons_area.y,
+            no.width() as u16,
+            1,
+        );
+
+        let buttons = Line::from(vec![
+            Span::raw("  "),
+            yes,
+            Span::raw("  "),
+            no,
+            Span::styled(format!("  {}", self.hint()), self.theme.muted),
+        ]);
+        f.render_widget(Paragraph::new(buttons), buttons_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::api::terminal::ScriptedEvents;
+
+    fn prompt() -> ConfirmPrompt {
+        ConfirmPrompt::new("Delete things?", vec![Line::from("  3 files")])
+    }
+
+    fn run(keys: impl IntoIterator<Item = KeyCode>) -> (bool, Vec<String>) {
+        let mut terminal = InlineTerminal::headless(70, 6, ScriptedEvents::keys(keys));
+        let result = prompt().run(&mut terminal).unwrap();
+        (result, terminal.rendered())
+    }
+
+    #[test]
+    fn defaults_to_no() {
+        assert!(!run([KeyCode::Enter]).0);
+    }
+
+    #[test]
+    fn left_then_enter_confirms() {
+        assert!(run([KeyCode::Left, KeyCode::Enter]).0);
+    }
+
+    #[test]
+    fn shortcuts_answer_immediately() {
+        assert!(run([KeyCode::Char('y')]).0);
+        assert!(!run([KeyCode::Char('n')]).0);
+        assert!(!run([KeyCode::Esc]).0);
+    }
+
+    #[test]
+    fn ctrl_c_cancels_even_on_yes() {
+        let ctrl_c = crossterm::event::KeyEvent::new(
+            KeyCode::Char('c'),
+            crossterm::event::KeyModifiers::CONTROL,
+        );
+        let events = ScriptedEvents::new([Event::Key(KeyCode::Left.into()), Event::Key(ctrl_c)]);
+        let mut terminal = InlineTerminal::headless(70, 6, events);
+        assert!(!prompt().run(&mut terminal).unwrap());
+    }
+
+    #[test]
+    fn renders_header_body_and_buttons() {
+        let (_, rendered) = run([KeyCode::Enter]);
+        assert_eq!(rendered[0], "Delete things?");
+        assert_eq!(rendered[2], "  3 files");
+        assert!(rendered[4].contains(" Yes, confirm "));
+        assert!(rendered[4].contains(" No, cancel "));
+    }
+
+    #[test]
+    fn clicking_a_button_answers() {
+        let click = |column| {
+            Event::Mouse(crossterm::event::MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row: 4,
+                modifiers: crossterm::event::KeyModifiers::NONE,
+            })
+        };
+
+        for (column, expected) in [(5, true), (20, false)] {
+            let events = ScriptedEvents::new([click(column)]);
+            let mut terminal = InlineTerminal::headless(70, 6, events);
+            assert_eq!(prompt().run(&mut terminal).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn long_bodies_wrap_and_scroll() {
+        let lines = (1..=10).map(|n| Line::from(format!("line {n}"))).collect();
+        let mut prompt = ConfirmPrompt::new("Lots", lines);
+        let keys = [
+            KeyCode::Down,
+            KeyCode::Down,
+            KeyCode::Char('G'),
+            KeyCode::Up,
+        ];
+        let keys = keys.into_iter().chain([KeyCode::Esc]);
+        let mut terminal = InlineTerminal::headless(40, 8, ScriptedEvents::keys(keys));
+        assert!(!prompt.run(&mut terminal).unwrap());
+
+        // Eight rows leave four for the body
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[2], "line 6");
+        assert_eq!(rendered[5], "line 9");
+        assert_eq!(rendered[6], "  lines 6-9 of 10");
+
+        let mut wrapped = ConfirmPrompt::new("Wrap", vec![Line::from("a ".repeat(30))]);
+        let mut terminal = InlineTerminal::headless(20, 8, ScriptedEvents::keys([KeyCode::Esc]));
+        wrapped.run(&mut terminal).unwrap();
+        assert_eq!(terminal.rendered()[2], "a a a a a a a a a a");
+        assert_eq!(terminal.rendered()[4], "a a a a a a a a a a");
+    }
+
+    #[test]
+    fn summarizes_changes() {
+        let prompt = ConfirmPrompt::changes(
+            "Save?",
+            vec![Line::from("  history.txt")],
+            &[
+                Change::Added("ls".into()),
+                Change::Removed("rm -rf /".into()),
+                Change::Removed("sl".into()),
+            ],
+        );
+        let body: Vec<String> = prompt.lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(
+            body,
+            [
+                "  history.txt",
+                "",
+                "  1 added, 2 removed",
+                "  + ls",
+                "  - rm -rf /",
+                "  - sl"
+            ]
+        );
+        assert_eq!(prompt.height(30), 11);
+    }
+
+    #[test]
+    fn plain_prompt_reads_answer() {
+        let mut output = Vec::new();
+        assert!(
+            prompt()
+                .run_plain(&mut "maybe\ny\n".as_bytes(), &mut output)
+                .unwrap()
+        );
+        assert!(
+            !prompt()
+                .run_plain(&mut "\n".as_bytes(), &mut output)
+                .unwrap()
+        );
+        assert!(prompt().run_plain(&mut "".as_bytes(), &mut output).is_err());
     }
 }
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use crossterm::event::{self, Event};
+
+/// Where the widgets read their input from.
+///
+/// The real terminal reads from crossterm, while tests feed a scripted
+/// sequence of events so the widget loops can run headless.
+pub(crate) trait EventSource {
+    /// Wait up to `timeout` for the next event, returning `None` if nothing
+    /// arrived in time.
+    fn next(&mut self, timeout: Duration) -> crate::Result<Option<Event>>;
+}
+
+/// Reads events from the terminal through crossterm.
+pub(crate) struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn next(&mut self, timeout: Duration) -> crate::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            return Ok(Some(event::read()?));
+        }
+        Ok(None)
+    }
+}
+
+/// Replays a fixed list of events, then errors so a test that never reaches a
+/// result fails instead of hanging.
+#[cfg(test)]
+pub(crate) struct ScriptedEvents {
+    events: std::collections::VecDeque<Event>,
+}
+
+#[cfg(test)]
+impl ScriptedEvents {
+    pub(crate) fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    /// Script plain key presses without modifiers.
+    pub(crate) fn keys(codes: impl IntoIterator<Item = event::KeyCode>) -> Self {
+        Self::new(
+            codes
+                .into_iter()
+                .map(|code| Event::Key(event::KeyEvent::from(code))),
+        )
+    }
+}
+
+#[cfg(test)]
+impl EventSource for ScriptedEvents {
+    fn next(&mut self, _timeout: Duration) -> crate::Result<Option<Event>> {
+        match self.events.pop_front() {
+            Some(event) => Ok(Some(event)),
+            None => Err(color_eyre::eyre::eyre!("scripted events ran out")),
+        }
+    }
+}
+
+/// Never produces an event, for widgets that run until something other than
+/// input finishes them.
+#[cfg(test)]
+pub(crate) struct NoEvents;
+
+#[cfg(test)]
+impl EventSource for NoEvents {
+    fn next(&mut self, timeout: Duration) -> crate::Result<Option<Event>> {
+        std::thread::sleep(timeout);
+        Ok(None)
+    }
+}
@@ -0,0 +1,479 @@
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use super::text_input::InputOutcome;
+use super::{
+    InlineTerminal, TerminalBackend, TerminalErrors, TextInput, Theme, is_interactive, is_interrupt,
+};
+
+enum Field {
+    Text(Box<TextInput>),
+    Select {
+        label: String,
+        options: Vec<String>,
+        selected: usize,
+    },
+    Confirm {
+        label: String,
+        value: bool,
+    },
+}
+
+impl Field {
+    fn label(&self) -> &str {
+        match self {
+            Field::Text(input) => input.label(),
+            Field::Select { label, .. } | Field::Confirm { label, .. } => label,
+        }
+    }
+}
+
+/// Several inputs, selects and yes/no toggles asked in one inline prompt.
+///
+/// Tab and Shift-Tab move between fields, Enter moves on and submits from the
+/// last field once every input validates.
+pub(crate) struct Form {
+    header: String,
+    fields: Vec<(String, Field)>,
+    focus: usize,
+    theme: Theme,
+}
+
+impl Form {
+    pub(crate) fn new(header: impl Into<String>) -> Self {
+        Self {
+            header: header.into(),
+            fields: Vec::new(),
+            focus: 0,
+            theme: Theme::current(),
+        }
+    }
+
+    /// Add a free-text field, read back with [`Self::value`].
+    pub(crate) fn input(mut self, key: impl Into<String>, input: TextInput) -> Self {
+        self.fields.push((key.into(), Field::Text(Box::new(input))));
+        self
+    }
+
+    /// Add a choice between `options`, read back with [`Self::value`].
+    pub(crate) fn select(
+        mut self,
+        key: impl Into<String>,
+        label: impl Into<String>,
+        options: Vec<String>,
+    ) -> Self {
+        let field = Field::Select {
+            label: label.into(),
+            options,
+            selected: 0,
+        };
+        self.fields.push((key.into(), field));
+        self
+    }
+
+    /// Add a yes/no toggle, read back with [`Self::flag`].
+    pub(crate) fn confirm(
+        mut self,
+        key: impl Into<String>,
+        label: impl Into<String>,
+        default: bool,
+    ) -> Self {
+        let field = Field::Confirm {
+            label: label.into(),
+            value: default,
+        };
+        self.fields.push((key.into(), field));
+        self
+    }
+
+    /// The text typed into an input, or the option picked in a select.
+    pub(crate) fn value(&self, key: &str) -> Option<&str> {
+        match self.field(key)? {
+            Field::Text(input) => Some(input.value()),
+            Field::Select {
+                options, selected, ..
+            } => options.get(*selected).map(String::as_str),
+            Field::Confirm { .. } => None,
+        }
+    }
+
+    pub(crate) fn flag(&self, key: &str) -> Option<bool> {
+        match self.field(key)? {
+            Field::Confirm { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn field(&self, key: &str) -> Option<&Field> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, f)| f)
+    }
+
+    /// Run the form inline, or field by field on stdin/stderr when stdout
+    /// isn't a terminal. Returns whether it was submitted.
+    pub(crate) fn prompt(&mut self, width: u16) -> crate::Result<bool> {
+        if !is_interactive() {
+            return self.run_plain(&mut std::io::stdin().lock(), &mut std::io::stderr());
+        }
+
+        let height = self.fields.len() as u16 + 4;
+        let mut terminal = InlineTerminal::new(height, width)?;
+        let result = self.run(&mut terminal)?;
+        terminal.cleanup()?;
+        Ok(result)
+    }
+
+    /// Run the non-interactive fallback, asking for each field in turn.
+    pub(crate) fn run_plain(
+        &mut self,
+        input: &mut impl std::io::BufRead,
+        output: &mut impl std::io::Write,
+    ) -> crate::Result<bool> {
+        writeln!(output, "{}", self.header)?;
+
+        for (i, (_, field)) in self.fields.iter_mut().enumerate() {
+            let answered = match field {
+                Field::Text(text) => match text.run_plain(input, output) {
+                    Ok(value) => value.is_some(),
+                    // Only the very first question may fail for lack of input
+                    Err(_) if i > 0 => false,
+                    Err(e) => return Err(e),
+                },
+                Field::Select {
+                    label,
+                    options,
+                    selected,
+                } => {
+                    for (n, option) in options.iter().enumerate() {
+                        writeln!(output, "{:>4}. {option}", n + 1)?;
+                    }
+                    ask(
+                        input,
+                        output,
+                        i,
+                        &format!("{label} [{}]", *selected + 1),
+                        |line| {
+                            let n = line.parse::<usize>().ok()?;
+                            (1..=options.len()).contains(&n).then(|| *selected = n - 1)
+                        },
+                    )?
+                }
+                Field::Confirm { label, value } => {
+                    let default = if *value { "Y/n" } else { "y/N" };
+                    ask(input, output, i, &format!("{label} [{default}]"), |line| {
+                        match line.to_lowercase().as_str() {
+                            "y" | "yes" => *value = true,
+                            "n" | "no" => *value = false,
+                            _ => return None,
+                        }
+                        Some(())
+                    })?
+                }
+            };
+
+            if !answered {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub(crate) fn run<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut InlineTerminal<B>,
+    ) -> crate::Result<bool> {
+        // Nothing to ask, and nothing for Tab or Enter to move between
+        if self.fields.is_empty() {
+            return Ok(true);
+        }
+
+        let label_width = self
+            .fields
+            .iter()
+            .map(|(_, f)| f.label().chars().count())
+            .max()
+            .unwrap_or(0);
+
+        loop {
+            let mut lines = vec![
+                Line::from(Span::styled(self.header.clone(), self.theme.header)),
+                Line::from(""),
+            ];
+
+            for (i, (_, field)) in self.fields.iter().enumerate() {
+                let focused = i == self.focus;
+                let marker = if focused { " > " } else { "   " };
+                let mut spans = vec![
+                    Span::raw(marker),
+                    Span::styled(
+                        format!("{:<label_width$}  ", field.label()),
+                        if focused {
+                            self.theme.header
+                        } else {
+                            self.theme.muted
+                        },
+                    ),
+                ];
+                spans.extend(self.field_spans(field, focused));
+                lines.push(Line::from(spans));
+            }
+
+            lines.push(Line::from(""));
+            lines.push(match self.focused_error() {
+                Some(error) => Line::from(Span::styled(format!("  {error}"), self.theme.error)),
+                None => Line::from(Span::styled(format!("  {}", self.hint()), self.theme.muted)),
+            });
+
+            terminal.draw(|f| f.render_widget(Paragraph::new(lines), f.area()))?;
+
+            let Some(Event::Key(key)) = terminal.next_event(Duration::from_millis(100))? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Tab => self.focus = (self.focus + 1) % self.fields.len(),
+                KeyCode::BackTab => {
+                    self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+                }
+                KeyCode::Esc => return Ok(false),
+                _ if is_interrupt(&key) => return Ok(false),
+                KeyCode::Enter if !matches!(self.fields[self.focus].1, Field::Text(_)) => {
+                    if self.advance() {
+                        return Ok(true);
+                    }
+                }
+                _ => match &mut self.fields[self.focus].1 {
+                    Field::Text(input) => match input.handle_key(key) {
+                        InputOutcome::Submitted => {
+                            if self.advance() {
+                                return Ok(true);
+                            }
+                        }
+                        InputOutcome::Cancelled => return Ok(false),
+                        InputOutcome::Editing => {}
+                    },
+                    // There's nothing to choose between without options
+                    Field::Select {
+                        options, selected, ..
+                    } if !options.is_empty() => match key.code {
+                        KeyCode::Left | KeyCode::Char('h') => {
+                            *selected = (*selected + options.len() - 1) % options.len();
+                        }
+                        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => {
+                            *selected = (*selected + 1) % options.len();
+                        }
+                        _ => {}
+                    },
+                    Field::Select { .. } => {}
+                    Field::Confirm { value, .. } => match key.code {
+                        KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') => *value = !*value,
+                        KeyCode::Char('y') => *value = true,
+                        KeyCode::Char('n') => *value = false,
+                        _ => {}
+                    },
+                },
+            }
+        }
+    }
+
+    /// Move to the next field, or check every input when on the last one.
+    /// Returns whether the form is ready to submit.
+    fn advance(&mut self) -> bool {
+        if self.focus + 1 < self.fields.len() {
+            self.focus += 1;
+            return false;
+        }
+
+        for (i, (_, field)) in self.fields.iter_mut().enumerate() {
+            if let Field::Text(input) = field
+                && !input.check()
+            {
+                self.focus = i;
+                return false;
+            }
+        }
+        true
+    }
+
+    fn focused_error(&self) -> Option<&str> {
+        match &self.fields.get(self.focus)?.1 {
+            Field::Text(input) => input.error(),
+            _ => None,
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self.fields.get(self.focus).map(|(_, f)| f) {
+            Some(Field::Text(_)) => "(Tab next field, Enter next/submit, Esc cancel, ↑/↓ history)",
+            Some(Field::Select { .. }) => {
+                "(←/→ choose, Tab next field, Enter next/submit, Esc cancel)"
+            }
+            _ => "(Space toggle, Tab next field, Enter next/submit, Esc cancel)",
+        }
+    }
+
+    fn field_spans(&self, field: &Field, focused: bool) -> Vec<Span<'static>> {
+        match field {
+            Field::Text(input) => input.spans(focused),
+            Field::Select {
+                options, selected, ..
+            } => options
+                .iter()
+                .enumerate()
+                .flat_map(|(i, option)| {
+                    let style = if i == *selected {
+                        self.theme.highlight
+                    } else {
+                        self.theme.muted
+                    };
+                    [Span::styled(format!(" {option} "), style), Span::raw(" ")]
+                })
+                .collect(),
+            Field::Confirm { value, .. } => {
+                let (check, style, text) = if *value {
+                    ("● ", self.theme.checked, "yes")
+                } else {
+                    ("○ ", self.theme.unchecked, "no")
+                };
+                vec![Span::styled(check, style), Span::raw(text)]
+            }
+        }
+    }
+}
+
+/// Ask a plain question until `answer` accepts the line, keeping the current
+/// value on an empty line. Returns false if input ran out.
+fn ask(
+    input: &mut impl std::io::BufRead,
+    output: &mut impl std::io::Write,
+    index: usize,
+    question: &str,
+    mut answer: impl FnMut(&str) -> Option<()>,
+) -> crate::Result<bool> {
+    loop {
+        write!(output, "{question}: ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            if index == 0 {
+                return Err(TerminalErrors::NoInput.into());
+            }
+            return Ok(false);
+        }
+
+        let line = line.trim();
+        if line.is_empty() || answer(line).is_some() {
+            return Ok(true);
+        }
+        writeln!(output, "Could not understand `{line}`")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+    use crate::api::terminal::ScriptedEvents;
+
+    fn form() -> Form {
+        Form::new("New command")
+            .input(
+                "name",
+                TextInput::new("Name").with_validation(|v| {
+                    if v.is_empty() {
+                        Err("Name is required".to_string())
+                    } else {
+                        Ok(())
+                    }
+                }),
+            )
+            .select(
+                "template",
+                "Template",
+                vec!["command".to_string(), "nested".to_string()],
+            )
+            .confirm("register", "Register", true)
+    }
+
+    fn keys(codes: impl IntoIterator<Item = KeyCode>) -> ScriptedEvents {
+        ScriptedEvents::new(codes.into_iter().map(|c| Event::Key(KeyEvent::from(c))))
+    }
+
+    #[test]
+    fn fills_fields_with_tab_and_enter() {
+        let events = keys([
+            KeyCode::Char('d'),
+            KeyCode::Char('b'),
+            KeyCode::Tab,
+            KeyCode::Right,
+            KeyCode::Enter,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ]);
+        let mut terminal = InlineTerminal::headless(70, 7, events);
+        let mut form = form();
+
+        assert!(form.run(&mut terminal).unwrap());
+        assert_eq!(form.value("name"), Some("db"));
+        assert_eq!(form.value("template"), Some("nested"));
+        assert_eq!(form.flag("register"), Some(false));
+    }
+
+    #[test]
+    fn submit_jumps_back_to_invalid_field() {
+        let events = keys([KeyCode::BackTab, KeyCode::Enter, KeyCode::Esc]);
+        let mut terminal = InlineTerminal::headless(70, 7, events);
+
+        assert!(!form().run(&mut terminal).unwrap());
+
+        let rendered = terminal.rendered();
+        assert!(rendered[2].starts_with(" > Name"));
+        assert_eq!(rendered[6], "  Name is required");
+    }
+
+    #[test]
+    fn empty_form_submits_right_away() {
+        let events = keys([KeyCode::Tab, KeyCode::BackTab, KeyCode::Enter]);
+        let mut terminal = InlineTerminal::headless(70, 7, events);
+
+        assert!(Form::new("Nothing").run(&mut terminal).unwrap());
+    }
+
+    #[test]
+    fn empty_select_ignores_arrows() {
+        let events = keys([KeyCode::Left, KeyCode::Right, KeyCode::Enter]);
+        let mut terminal = InlineTerminal::headless(70, 5, events);
+        let mut form = Form::new("Pick").select("template", "Template", Vec::new());
+
+        assert!(form.run(&mut terminal).unwrap());
+        assert_eq!(form.value("template"), None);
+    }
+
+    #[test]
+    fn plain_form_asks_each_field() {
+        let mut form = form();
+        let mut output = Vec::new();
+
+        let submitted = form
+            .run_plain(&mut "\nwidget\n2\nn\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert!(submitted);
+        assert_eq!(form.value("name"), Some("widget"));
+        assert_eq!(form.value("template"), Some("nested"));
+        assert_eq!(form.flag("register"), Some(false));
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Name is required")
+        );
+    }
+}
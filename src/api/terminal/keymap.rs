@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a widget can do in response to a key press.
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Action {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    Toggle,
+    Search,
+    Confirm,
+    Cancel,
+    /// Move focus to the confirm button
+    FocusConfirm,
+    /// Move focus to the cancel button
+    FocusCancel,
+    /// Flip focus between the confirm and cancel buttons
+    SwitchButton,
+    /// Answer a confirmation prompt with yes right away
+    Yes,
+    /// Answer a confirmation prompt with no right away
+    No,
+    /// Scroll a preview pane without moving the cursor
+    PreviewUp,
+    PreviewDown,
+    SelectAll,
+    SelectNone,
+    InvertSelection,
+    /// Start marking a range of rows, or toggle the marked range
+    VisualRange,
+    /// Select every row matching the search, or deselect them if they
+    /// already are
+    ToggleFiltered,
+    /// Sort a table by its next column
+    SortNext,
+    /// Flip the order a table is sorted in
+    SortReverse,
+    /// Collapse or expand the group under the cursor
+    Fold,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Preset {
+    #[default]
+    Vim,
+    Emacs,
+    Arrows,
+}
+
+/// The `[keymap]` section of the config file.
+///
+/// ```toml
+/// [keymap]
+/// preset = "emacs"
+///
+/// [keymap.bindings]
+/// confirm = ["enter", "ctrl-j"]
+/// ```
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct KeymapConfig {
+    preset: Preset,
+    /// Replaces the preset's keys for each listed action
+    bindings: HashMap<Action, Vec<String>>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum KeymapErrors {
+    #[error("Unknown key `{0}` in keymap, try something like `ctrl-n`, `enter` or `j`")]
+    UnknownKey(String),
+}
+
+/// A key plus the modifiers that have to be held with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    const fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    const fn char(c: char) -> Self {
+        Self::key(KeyCode::Char(c))
+    }
+
+    const fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    const fn alt(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::ALT)
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // Shift is already part of an uppercase or symbol character, and
+        // terminals disagree on whether to report it
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+impl std::str::FromStr for KeyChord {
+    type Err = KeymapErrors;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || KeymapErrors::UnknownKey(s.to_string());
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl-") {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt-") {
+                (KeyModifiers::ALT, 4)
+            } else if lower.starts_with("shift-") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+
+            // A lone `ctrl-` etc. has no key left to bind
+            if rest.len() == len {
+                return Err(unknown());
+            }
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(unknown()),
+                }
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl std::fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+
+        match self.code {
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Maps key chords to widget actions.
+#[derive(Clone, Debug)]
+pub(crate) struct Keymap {
+    bindings: HashMap<Action, Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(Preset::default())
+    }
+}
+
+impl Keymap {
+    pub(crate) fn preset(preset: Preset) -> Self {
+        use Action::*;
+
+        // Arrow keys and friends work in every preset
+        let mut bindings = HashMap::from([
+            (Up, vec![KeyChord::key(KeyCode::Up)]),
+            (Down, vec![KeyChord::key(KeyCode::Down)]),
+            (Top, vec![KeyChord::key(KeyCode::Home)]),
+            (Bottom, vec![KeyChord::key(KeyCode::End)]),
+            (Toggle, vec![KeyChord::char(' ')]),
+            (Search, vec![KeyChord::char('/')]),
+            (Confirm, vec![KeyChord::key(KeyCode::Enter)]),
+            (Cancel, vec![KeyChord::key(KeyCode::Esc)]),
+            (FocusConfirm, vec![KeyChord::key(KeyCode::Left)]),
+            (FocusCancel, vec![KeyChord::key(KeyCode::Right)]),
+            (SwitchButton, vec![KeyChord::key(KeyCode::Tab)]),
+            (Yes, vec![]),
+            (No, vec![]),
+            (
+                PreviewUp,
+                vec![KeyChord::new(KeyCode::Up, KeyModifiers::SHIFT)],
+            ),
+            (
+                PreviewDown,
+                vec![KeyChord::new(KeyCode::Down, KeyModifiers::SHIFT)],
+            ),
+            (SelectAll, vec![KeyChord::alt('a')]),
+            (SelectNone, vec![KeyChord::alt('d')]),
+            (InvertSelection, vec![KeyChord::alt('i')]),
+            (VisualRange, vec![KeyChord::alt('v')]),
+            (ToggleFiltered, vec![KeyChord::alt('t')]),
+            (SortNext, vec![KeyChord::alt('s')]),
+            (SortReverse, vec![KeyChord::alt('r')]),
+            (Fold, vec![KeyChord::alt('z')]),
+        ]);
+
+        let extra: &[(Action, KeyChord)] = match preset {
+            Preset::Vim => &[
+                (Up, KeyChord::char('k')),
+                (Down, KeyChord::char('j')),
+                (Top, KeyChord::char('g')),
+                (Bottom, KeyChord::char('G')),
+                (Cancel, KeyChord::char('q')),
+                (FocusConfirm, KeyChord::char('h')),
+                (FocusCancel, KeyChord::char('l')),
+                (Yes, KeyChord::char('y')),
+                (Yes, KeyChord::char('Y')),
+                (No, KeyChord::char('n')),
+                (No, KeyChord::char('N')),
+                (PreviewUp, KeyChord::ctrl('y')),
+                (PreviewDown, KeyChord::ctrl('e')),
+                (SelectAll, KeyChord::char('A')),
+                (SelectNone, KeyChord::char('D')),
+                (InvertSelection, KeyChord::char('I')),
+                (VisualRange, KeyChord::char('V')),
+                (ToggleFiltered, KeyChord::char('T')),
+                (SortNext, KeyChord::char('s')),
+                (SortReverse, KeyChord::char('S')),
+                (Fold, KeyChord::char('z')),
+            ],
+            Preset::Emacs => &[
+                (Up, KeyChord::ctrl('p')),
+                (Down, KeyChord::ctrl('n')),
+                (Top, KeyChord::alt('<')),
+                (Bottom, KeyChord::alt('>')),
+                (Search, KeyChord::ctrl('s')),
+                (Cancel, KeyChord::ctrl('g')),
+                (FocusConfirm, KeyChord::ctrl('b')),
+                (FocusCancel, KeyChord::ctrl('f')),
+                (Yes, KeyChord::char('y')),
+                (No, KeyChord::char('n')),
+                (PreviewUp, KeyChord::alt('p')),
+                (PreviewDown, KeyChord::alt('n')),
+                (VisualRange, KeyChord::ctrl(' ')),
+            ],
+            Preset::Arrows => &[],
+        };
+
+        for (action, chord) in extra {
+            bindings.entry(*action).or_default().push(*chord);
+        }
+
+        Self { bindings }
+    }
+
+    /// The keymap described by the config file.
+    pub(crate) fn from_config(config: &KeymapConfig) -> Result<Self, KeymapErrors> {
+        let mut keymap = Self::preset(config.preset);
+        for (action, keys) in &config.bindings {
+            let chords = keys
+                .iter()
+                .map(|k| k.parse())
+                .collect::<Result<Vec<KeyChord>, _>>()?;
+            keymap.bindings.insert(*action, chords);
+        }
+        Ok(keymap)
+    }
+
+    /// The keymap from the user's config, falling back to the default preset.
+    pub(crate) fn current() -> Self {
+        // Bad bindings are rejected when the config is loaded in `main`
+        Self::from_config(&crate::api::config::get().keymap).unwrap_or_default()
+    }
+
+    /// The first action in `actions` bound to `key`.
+    ///
+    /// Widgets pass only the actions that make sense in their current state,
+    /// so the same key can mean different things in different widgets.
+    pub(crate) fn action(&self, key: &KeyEvent, actions: &[Action]) -> Option<Action> {
+        actions.iter().copied().find(|action| {
+            self.bindings
+                .get(action)
+                .is_some_and(|chords| chords.iter().any(|c| c.matches(key)))
+        })
+    }
+
+    /// Render a hint such as `(↑/↓ navigate, Enter confirm)` from the first
+    /// key bound to each action. Actions without keys are left out.
+    pub(crate) fn hint(&self, entries: &[(&[Action], &str)]) -> String {
+        let parts: Vec<String> = entries
+            .iter()
+            .filter_map(|(actions, label)| {
+                let keys: Vec<String> = actions
+                    .iter()
+                    .filter_map(|a| self.bindings.get(a)?.first())
+                    .map(|c| c.to_string())
+                    .collect();
+                (!keys.is_empty()).then(|| format!("{} {label}", keys.join("/")))
+            })
+            .collect();
+
+        format!("({})", parts.join(", "))
+    }
+
+    /// Check that every key in the config parses, so mistakes surface at
+    /// startup rather than silently falling back to the defaults.
+    pub(crate) fn validate(config: &KeymapConfig) -> Result<(), KeymapErrors> {
+        Self::from_config(config).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_chords() {
+        assert_eq!("j".parse::<KeyChord>().unwrap(), KeyChord::char('j'));
+        assert_eq!("G".parse::<KeyChord>().unwrap(), KeyChord::char('G'));
+        assert_eq!("ctrl-n".parse::<KeyChord>().unwrap(), KeyChord::ctrl('n'));
+        assert_eq!("Alt-<".parse::<KeyChord>().unwrap(), KeyChord::alt('<'));
+        assert_eq!(
+            "enter".parse::<KeyChord>().unwrap(),
+            KeyChord::key(KeyCode::Enter)
+        );
+        assert!("ctrl-".parse::<KeyChord>().is_err());
+        assert!("hyper-x".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn presets_bind_their_keys() {
+        let all = [Action::Up, Action::Down, Action::Cancel];
+        let j = press(KeyCode::Char('j'), KeyModifiers::NONE);
+        let ctrl_n = press(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        let down = press(KeyCode::Down, KeyModifiers::NONE);
+
+        let vim = Keymap::preset(Preset::Vim);
+        assert_eq!(vim.action(&j, &all), Some(Action::Down));
+        assert_eq!(vim.action(&ctrl_n, &all), None);
+
+        let emacs = Keymap::preset(Preset::Emacs);
+        assert_eq!(emacs.action(&j, &all), None);
+        assert_eq!(emacs.action(&ctrl_n, &all), Some(Action::Down));
+
+        let arrows = Keymap::preset(Preset::Arrows);
+        assert_eq!(arrows.action(&j, &all), None);
+        assert_eq!(arrows.action(&down, &all), Some(Action::Down));
+    }
+
+    #[test]
+    fn only_offered_actions_match() {
+        let keymap = Keymap::preset(Preset::Vim);
+        let y = press(KeyCode::Char('y'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&y, &[Action::Confirm]), None);
+        assert_eq!(keymap.action(&y, &[Action::Yes]), Some(Action::Yes));
+    }
+
+    #[test]
+    fn shifted_characters_match_without_shift() {
+        let keymap = Keymap::preset(Preset::Vim);
+        let g = press(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&g, &[Action::Bottom]), Some(Action::Bottom));
+    }
+
+    #[test]
+    fn config_overrides_preset() {
+        let config: KeymapConfig = toml::from_str(
+            r#"
+            preset = "arrows"
+            [bindings]
+            confirm = ["ctrl-j"]
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::from_config(&config).unwrap();
+
+        let enter = press(KeyCode::Enter, KeyModifiers::NONE);
+        let ctrl_j = press(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&enter, &[Action::Confirm]), None);
+        assert_eq!(
+            keymap.action(&ctrl_j, &[Action::Confirm]),
+            Some(Action::Confirm)
+        );
+        assert_eq!(
+            keymap.hint(&[(&[Action::Confirm], "confirm")]),
+            "(C-j confirm)"
+        );
+    }
+
+    #[test]
+    fn hint_skips_unbound_actions() {
+        let keymap = Keymap::preset(Preset::Arrows);
+        let hint = keymap.hint(&[
+            (&[Action::Up, Action::Down], "navigate"),
+            (&[Action::Yes], "yes"),
+        ]);
+        assert_eq!(hint, "(↑/↓ navigate)");
+    }
+}
@@ -1,23 +1,138 @@
+mod app;
 mod confirm;
+mod events;
+mod form;
+mod keymap;
+mod progress;
 mod select_list;
+mod select_table;
+mod text_input;
+mod theme;
+mod tty;
 
-#[allow(unused_imports)]
-pub(crate) use confirm::ConfirmPrompt;
-pub(crate) use select_list::{SelectList, SelectResult};
+pub(crate) use confirm::{Change, ConfirmPrompt};
+pub(crate) use events::{CrosstermEvents, EventSource};
+#[cfg(test)]
+pub(crate) use events::{NoEvents, ScriptedEvents};
+pub(crate) use form::Form;
+pub(crate) use keymap::{Action, Keymap, KeymapConfig};
+pub(crate) use progress::Progress;
+pub(crate) use select_list::{Feed, PreviewPosition, SelectList, SelectResult};
+pub(crate) use select_table::{Column, SelectTable};
+pub(crate) use text_input::TextInput;
+pub(crate) use theme::{Theme, ThemeConfig};
+pub(crate) use tty::{defer_signals, install_hooks};
 
-use crossterm::{ExecutableCommand, cursor, terminal};
+use crossterm::{
+    ExecutableCommand, cursor,
+    event::{EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal,
+};
 use ratatui::{TerminalOptions, Viewport, prelude::*};
-use std::io::stdout;
+use std::io::{IsTerminal, Stdout, stdout};
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
 
-pub(crate) struct InlineTerminal {
-    terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum TerminalErrors {
+    #[error("stdout is not a terminal, so the interactive prompt can't be drawn")]
+    NotATty,
+    #[error(
+        "Not running in a terminal and nothing was read from stdin, rerun with --save or --yes to skip the prompt"
+    )]
+    NoInput,
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Whether stdout is attached to a terminal that the inline widgets can draw
+/// to. When it isn't, widgets fall back to a plain prompt on stdin.
+pub(crate) fn is_interactive() -> bool {
+    stdout().is_terminal()
+}
+
+/// Whether `key` is Ctrl+C, which raw mode delivers as a key press instead
+/// of a signal. Every widget treats it as cancelling outright.
+pub(crate) fn is_interrupt(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// How many rows `text` takes wrapped at word boundaries to `width` columns,
+/// as a [`Paragraph`](ratatui::widgets::Paragraph) wrapping without trimming
+/// lays it out. Words wider than a row are broken across rows.
+fn wrapped_height(text: &Text, width: u16) -> usize {
+    let width = usize::from(width.max(1));
+    text.lines
+        .iter()
+        .map(|line| {
+            let content: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+            let (mut rows, mut used) = (1, 0);
+            for word in content.split_inclusive(' ') {
+                let visible = word.trim_end_matches(' ').width();
+                if used > 0 && used + visible > width {
+                    rows += 1;
+                    used = 0;
+                }
+                used += visible;
+                while used > width {
+                    rows += 1;
+                    used -= width;
+                }
+                used += word.len() - word.trim_end_matches(' ').len();
+            }
+            rows
+        })
+        .sum()
+}
+
+/// Turn on mouse capture if the config asks for it, returning whether it did.
+fn enable_mouse() -> crate::Result<bool> {
+    let mouse = crate::api::config::get().mouse;
+    if mouse {
+        stdout().execute(EnableMouseCapture)?;
+    }
+    Ok(mouse)
+}
+
+/// A ratatui backend whose errors can be reported through `crate::Result`.
+pub(crate) trait TerminalBackend:
+    Backend<Error: std::error::Error + Send + Sync + 'static>
+{
+}
+
+impl<B> TerminalBackend for B where B: Backend<Error: std::error::Error + Send + Sync + 'static> {}
+
+/// A terminal the widgets draw into, either a fixed area below the cursor
+/// or the whole alternate screen.
+pub(crate) struct InlineTerminal<B: Backend = CrosstermBackend<Stdout>> {
+    terminal: Terminal<B>,
+    events: Box<dyn EventSource>,
     start_row: u16,
+    /// The size asked for, which the viewport shrinks below when the
+    /// terminal is too small
+    requested: (u16, u16),
+    /// Whether this terminal is drawing on the alternate screen
+    fullscreen: bool,
+    /// Whether this terminal put the tty into raw mode and has to restore it
+    owns_tty: bool,
     cleaned_up: bool,
 }
 
 impl InlineTerminal {
+    /// Reserve `height` rows below the cursor, clamped to the terminal size.
     pub(crate) fn new(height: u16, width: u16) -> crate::Result<Self> {
+        // Raw mode and cursor queries hang or fail without a terminal
+        if !is_interactive() {
+            return Err(TerminalErrors::NotATty.into());
+        }
+
+        let (cols, rows) = terminal::size()?;
+        let requested = (height, width);
+        let height = height.min(rows);
+        let width = width.min(cols);
+
         terminal::enable_raw_mode()?;
+        let mouse = enable_mouse()?;
 
         for _ in 0..height {
             println!();
@@ -26,6 +141,11 @@ impl InlineTerminal {
         stdout().execute(cursor::MoveUp(height))?;
 
         let start_row = cursor::position()?.1;
+        tty::claim(tty::TtyState {
+            fullscreen: false,
+            mouse,
+            start_row,
+        });
 
         let backend = CrosstermBackend::new(stdout());
         let terminal = Terminal::with_options(
@@ -37,30 +157,145 @@ impl InlineTerminal {
 
         Ok(Self {
             terminal,
+            events: Box::new(CrosstermEvents),
             start_row,
+            requested,
+            fullscreen: false,
+            owns_tty: true,
             cleaned_up: false,
         })
     }
 
+    /// Take over the whole terminal on the alternate screen, for lists too
+    /// long to be useful inline. The scrollback is left untouched.
+    pub(crate) fn fullscreen() -> crate::Result<Self> {
+        if !is_interactive() {
+            return Err(TerminalErrors::NotATty.into());
+        }
+
+        terminal::enable_raw_mode()?;
+        stdout().execute(terminal::EnterAlternateScreen)?;
+        let mouse = enable_mouse()?;
+        tty::claim(tty::TtyState {
+            fullscreen: true,
+            mouse,
+            start_row: 0,
+        });
+
+        let backend = CrosstermBackend::new(stdout());
+        let terminal = Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Fullscreen,
+            },
+        )?;
+
+        Ok(Self {
+            terminal,
+            events: Box::new(CrosstermEvents),
+            start_row: 0,
+            requested: (u16::MAX, u16::MAX),
+            fullscreen: true,
+            owns_tty: true,
+            cleaned_up: false,
+        })
+    }
+}
+
+#[cfg(test)]
+impl InlineTerminal<ratatui::backend::TestBackend> {
+    /// An in-memory terminal driven by `events`, for testing widgets.
+    pub(crate) fn headless(width: u16, height: u16, events: impl EventSource + 'static) -> Self {
+        let backend = ratatui::backend::TestBackend::new(width, height);
+        let terminal = Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Fixed(Rect::new(0, 0, width, height)),
+            },
+        )
+        .expect("test backend is infallible");
+        Self {
+            terminal,
+            events: Box::new(events),
+            start_row: 0,
+            requested: (height, width),
+            fullscreen: false,
+            owns_tty: false,
+            cleaned_up: false,
+        }
+    }
+
+    /// The last rendered frame, one string per row with trailing spaces
+    /// trimmed.
+    pub(crate) fn rendered(&self) -> Vec<String> {
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+impl<B: TerminalBackend> InlineTerminal<B> {
     pub(crate) fn draw(&mut self, f: impl FnOnce(&mut Frame)) -> crate::Result<()> {
         self.terminal.draw(f)?;
         Ok(())
     }
 
-    pub(crate) fn cleanup(&mut self) -> crate::Result<()> {
-        if self.cleaned_up {
+    /// Wait up to `timeout` for the next input event.
+    ///
+    /// Resizes are handled here by reflowing the viewport before they're
+    /// passed on, so widgets only have to redraw.
+    pub(crate) fn next_event(&mut self, timeout: Duration) -> crate::Result<Option<Event>> {
+        let event = self.events.next(timeout)?;
+        if let Some(Event::Resize(cols, rows)) = event {
+            self.resize(cols, rows)?;
+        }
+        Ok(event)
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) -> crate::Result<()> {
+        if self.fullscreen {
+            self.terminal.autoresize()?;
             return Ok(());
         }
-        self.cleaned_up = true;
 
-        terminal::disable_raw_mode()?;
-        stdout().execute(cursor::MoveTo(0, self.start_row))?;
-        stdout().execute(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        let (height, width) = self.requested;
+        let height = height.min(rows);
+        // Keep the whole viewport on screen when the terminal gets shorter
+        let y = self.start_row.min(rows - height);
+        self.terminal
+            .resize(Rect::new(0, y, width.min(cols), height))?;
+
+        // Shrinking the width clears the screen and moves the viewport to
+        // the top, so cleanup has to follow it there
+        self.start_row = self.terminal.get_frame().area().y;
+        if self.owns_tty {
+            tty::move_viewport(self.start_row);
+        }
         Ok(())
     }
 }
 
-impl Drop for InlineTerminal {
+impl<B: Backend> InlineTerminal<B> {
+    pub(crate) fn cleanup(&mut self) -> crate::Result<()> {
+        if self.cleaned_up || !self.owns_tty {
+            return Ok(());
+        }
+        self.cleaned_up = true;
+        tty::restore()
+    }
+}
+
+impl<B: Backend> Drop for InlineTerminal<B> {
     fn drop(&mut self) {
         let _ = self.cleanup();
     }
@@ -0,0 +1,362 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyEventKind};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use super::{
+    InlineTerminal, TerminalBackend, TerminalErrors, Theme, defer_signals, is_interactive,
+    is_interrupt, tty,
+};
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const BAR_WIDTH: usize = 24;
+
+enum Update {
+    Message(String),
+    Advance(u64),
+}
+
+/// Handed to the background work to report how it's getting on.
+pub(crate) struct Reporter {
+    tx: Sender<Update>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Reporter {
+    /// Show what the work is currently doing next to the label.
+    pub(crate) fn message(&self, message: impl Into<String>) {
+        // The widget only stops listening once the work has returned
+        let _ = self.tx.send(Update::Message(message.into()));
+    }
+
+    /// Move a progress bar `n` steps along.
+    pub(crate) fn advance(&self, n: u64) {
+        let _ = self.tx.send(Update::Advance(n));
+    }
+
+    /// Fail with [`TerminalErrors::Interrupted`] once Ctrl+C, SIGTERM or
+    /// SIGHUP has asked [`Progress::cancellable`] work to stop, to check
+    /// between steps.
+    pub(crate) fn check_cancelled(&self) -> crate::Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) || tty::signalled() {
+            return Err(TerminalErrors::Interrupted.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    message: Option<String>,
+    done: u64,
+    frame: usize,
+}
+
+impl State {
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Message(message) => self.message = Some(message),
+            Update::Advance(n) => self.done += n,
+        }
+    }
+}
+
+/// A spinner or progress bar shown on one line while work runs on a
+/// background thread, collapsing to a ✅/❌ summary once it's done.
+///
+/// Without a terminal it logs the work's messages as plain lines on stderr
+/// instead, so piped output stays clean.
+pub(crate) struct Progress {
+    label: String,
+    /// The number of steps of a progress bar, a spinner has none
+    total: Option<u64>,
+    summary: Option<String>,
+    /// Whether Ctrl+C waits for the work to stop instead of leaving it
+    cancellable: bool,
+    theme: Theme,
+}
+
+impl Progress {
+    pub(crate) fn spinner(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            total: None,
+            summary: None,
+            cancellable: false,
+            theme: Theme::current(),
+        }
+    }
+
+    /// A bar filled by [`Reporter::advance`] up to `total`.
+    pub(crate) fn bar(label: impl Into<String>, total: u64) -> Self {
+        Self {
+            total: Some(total),
+            ..Self::spinner(label)
+        }
+    }
+
+    /// What to say when the work succeeds, instead of repeating the label.
+    pub(crate) fn with_summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Have Ctrl+C, SIGTERM and SIGHUP ask the work to stop through
+    /// [`Reporter::check_cancelled`] and wait for it to, for work that has to
+    /// clean up after itself.
+    pub(crate) fn cancellable(mut self) -> Self {
+        self.cancellable = true;
+        self
+    }
+
+    /// Run `work` on a background thread, drawing progress until it returns.
+    pub(crate) fn show<R: Send + 'static>(
+        &self,
+        work: impl FnOnce(&Reporter) -> crate::Result<R> + Send + 'static,
+    ) -> crate::Result<R> {
+        // Signals wait for cancellable work to stop, and exit after that
+        let _signals = self.cancellable.then(defer_signals);
+        if !is_interactive() {
+            return self.run_plain(&mut std::io::stderr(), work);
+        }
+
+        let mut terminal = InlineTerminal::new(1, 120)?;
+        let result = self.run(&mut terminal, work);
+        terminal.cleanup()?;
+        println!("{}", self.summarize(&result));
+        result
+    }
+
+    /// Run the non-interactive fallback, logging progress to `output`.
+    pub(crate) fn run_plain<R: Send + 'static>(
+        &self,
+        output: &mut impl std::io::Write,
+        work: impl FnOnce(&Reporter) -> crate::Result<R> + Send + 'static,
+    ) -> crate::Result<R> {
+        writeln!(output, "{}...", self.label)?;
+
+        let (rx, handle, _) = spawn(work);
+        let mut state = State::default();
+        let mut logged_tenth = 0;
+        // Blocks until the work drops its reporter by returning
+        while let Ok(update) = rx.recv() {
+            state.apply(update);
+            match (&state.message, self.total) {
+                (Some(message), _) => writeln!(output, "  {message}")?,
+                // Log bar progress every 10% rather than every step
+                (None, Some(total)) if total > 0 && state.done * 10 / total > logged_tenth => {
+                    logged_tenth = state.done * 10 / total;
+                    writeln!(output, "  {}/{total}", state.done.min(total))?;
+                }
+                _ => {}
+            }
+            state.message = None;
+        }
+
+        let result = join(handle);
+        writeln!(output, "{}", self.summarize(&result))?;
+        result
+    }
+
+    /// Run the interactive loop, redrawing until the work returns.
+    pub(crate) fn run<R: Send + 'static, B: TerminalBackend>(
+        &self,
+        terminal: &mut InlineTerminal<B>,
+        work: impl FnOnce(&Reporter) -> crate::Result<R> + Send + 'static,
+    ) -> crate::Result<R> {
+        let (rx, handle, cancelled) = spawn(work);
+        let mut state = State::default();
+
+        loop {
+            if drain(&rx, &mut state) {
+                return join(handle);
+            }
+
+            terminal.draw(|f| self.render(&state, f))?;
+
+            // Raw mode swallows the interrupt signal, so look for it here.
+            // Unless the work can be cancelled, its thread is left to die
+            // with the process.
+            if let Some(Event::Key(key)) = terminal.next_event(Duration::from_millis(80))?
+                && key.kind == KeyEventKind::Press
+                && is_interrupt(&key)
+            {
+                if !self.cancellable {
+                    return Err(TerminalErrors::Interrupted.into());
+                }
+                cancelled.store(true, Ordering::Relaxed);
+                state.message = Some("cancelling…".to_string());
+                terminal.draw(|f| self.render(&state, f))?;
+                return join(handle);
+            }
+
+            state.frame += 1;
+        }
+    }
+
+    fn render(&self, state: &State, f: &mut Frame) {
+        let mut line = match self.total {
+            None => Line::from(vec![
+                Span::styled(SPINNER[state.frame % SPINNER.len()], self.theme.accent),
+                Span::raw(format!(" {}", self.label)),
+            ]),
+            Some(total) => {
+                let done = state.done.min(total);
+                let filled = (done * BAR_WIDTH as u64)
+                    .checked_div(total)
+                    .unwrap_or(BAR_WIDTH as u64) as usize;
+                Line::from(vec![
+                    Span::raw(format!("{} ", self.label)),
+                    Span::styled("█".repeat(filled), self.theme.accent),
+                    Span::styled("░".repeat(BAR_WIDTH - filled), self.theme.muted),
+                    Span::raw(format!(" {done}/{total}")),
+                ])
+            }
+        };
+
+        if let Some(message) = &state.message {
+            line.push_span(Span::styled(format!("  {message}"), self.theme.muted));
+        }
+
+        f.render_widget(Paragraph::new(line), f.area());
+    }
+
+    fn summarize<R>(&self, result: &crate::Result<R>) -> String {
+        match result {
+            Ok(_) => format!("✅ {}", self.summary.as_ref().unwrap_or(&self.label)),
+            Err(_) => format!("❌ {} failed", self.label),
+        }
+    }
+}
+
+fn spawn<R: Send + 'static>(
+    work: impl FnOnce(&Reporter) -> crate::Result<R> + Send + 'static,
+) -> (
+    Receiver<Update>,
+    JoinHandle<crate::Result<R>>,
+    Arc<AtomicBool>,
+) {
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let reporter = Reporter {
+        tx,
+        cancelled: cancelled.clone(),
+    };
+    let handle = std::thread::spawn(move || work(&reporter));
+    (rx, handle, cancelled)
+}
+
+/// Apply every pending update, returning whether the work has finished.
+fn drain(rx: &Receiver<Update>, state: &mut State) -> bool {
+    loop {
+        match rx.try_recv() {
+            Ok(update) => state.apply(update),
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => return true,
+        }
+    }
+}
+
+fn join<R>(handle: JoinHandle<crate::Result<R>>) -> crate::Result<R> {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::api::terminal::{NoEvents, ScriptedEvents};
+
+    #[test]
+    fn runs_work_in_the_background() {
+        let mut terminal = InlineTerminal::headless(60, 1, NoEvents);
+        let result = Progress::spinner("Counting").run(&mut terminal, |progress| {
+            progress.message("almost there");
+            std::thread::sleep(Duration::from_millis(200));
+            Ok(42)
+        });
+
+        assert_eq!(result.unwrap(), 42);
+        assert!(terminal.rendered()[0].contains("Counting  almost there"));
+    }
+
+    #[test]
+    fn cancelling_waits_for_the_work() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let events = ScriptedEvents::new([Event::Key(ctrl_c)]);
+        let mut terminal = InlineTerminal::headless(60, 1, events);
+        let (tx, rx) = mpsc::channel();
+
+        let result =
+            Progress::spinner("Waiting")
+                .cancellable()
+                .run(&mut terminal, move |progress| {
+                    loop {
+                        if let Err(error) = progress.check_cancelled() {
+                            tx.send("cleaned up").unwrap();
+                            return Err::<(), _>(error);
+                        }
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                });
+
+        assert_eq!(result.unwrap_err().to_string(), "Interrupted");
+        assert_eq!(rx.try_recv(), Ok("cleaned up"));
+        assert!(terminal.rendered()[0].contains("cancelling…"));
+    }
+
+    #[test]
+    fn renders_a_bar() {
+        let mut terminal = InlineTerminal::headless(60, 1, NoEvents);
+        let progress = Progress::bar("Steps", 4);
+        let state = State {
+            message: Some("writing".into()),
+            done: 1,
+            frame: 0,
+        };
+        terminal.draw(|f| progress.render(&state, f)).unwrap();
+
+        let row = &terminal.rendered()[0];
+        assert!(row.starts_with(&format!("Steps {}{}", "█".repeat(6), "░".repeat(18))));
+        assert!(row.ends_with(" 1/4  writing"));
+    }
+
+    #[test]
+    fn plain_output_logs_lines() {
+        let mut output = Vec::new();
+        let progress = Progress::bar("Copying", 2).with_summary("Copied everything");
+        progress
+            .run_plain(&mut output, |progress| {
+                progress.message("first");
+                progress.advance(1);
+                progress.advance(1);
+                Ok(())
+            })
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "Copying...\n  first\n  1/2\n  2/2\n✅ Copied everything\n"
+        );
+
+        let mut output = Vec::new();
+        let result = Progress::spinner("Breaking")
+            .run_plain(&mut output, |_| -> crate::Result<()> {
+                Err(color_eyre::eyre::eyre!("broken"))
+            });
+        assert!(result.is_err());
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .ends_with("❌ Breaking failed\n")
+        );
+    }
+}
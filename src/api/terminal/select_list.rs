@@ -1,16 +1,112 @@
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use ratatui::{
     prelude::*,
-    widgets::{List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap},
 };
 
-use super::InlineTerminal;
+use super::app::{self, App, Message};
+use super::select_table::{Column, Table};
+use super::{
+    Action, InlineTerminal, Keymap, TerminalBackend, TerminalErrors, Theme, is_interactive,
+    is_interrupt, wrapped_height,
+};
 
+/// How a [`SelectList`] was answered.
+#[derive(Debug, PartialEq)]
 pub(crate) enum SelectResult {
-    Confirmed,
+    /// The index into the items of the row confirmed in a list that isn't
+    /// toggleable
+    Picked(usize),
+    /// The indices of every selected item of a toggleable list, in item order
+    Many(Vec<usize>),
     Cancelled,
 }
 
+/// Sent to a list while it's on screen, see [`SelectList::with_feed`].
+pub(crate) enum Feed<T> {
+    /// More items to add to the end of the list
+    Items(Vec<T>),
+    /// What the sending side is up to, shown under the header, or `None` to
+    /// clear it
+    Status(Option<String>),
+}
+
+type RowFn<T> = Box<dyn Fn(&T) -> Line<'static>>;
+type GroupFn<T> = Box<dyn Fn(&T) -> String>;
+type PreviewFn<T> = Box<dyn Fn(&T) -> Text<'static>>;
+
+/// Where the preview pane sits relative to the list.
+#[derive(Clone, Copy)]
+pub(crate) enum PreviewPosition {
+    Right,
+    Bottom,
+}
+
+struct Preview<T> {
+    preview_fn: PreviewFn<T>,
+    position: PreviewPosition,
+    /// Lines scrolled past at the top of the pane
+    scroll: u16,
+    /// The item `scroll` applies to, so moving the cursor starts at the top
+    item: Option<usize>,
+}
+
+/// Where things were drawn last frame, so mouse events can be mapped back
+/// to rows and buttons.
+#[derive(Default)]
+struct HitAreas {
+    list: Rect,
+    preview: Option<Rect>,
+    confirm: Option<Rect>,
+    cancel: Option<Rect>,
+}
+
+/// A row of the list: an item, or the header of a group of items.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Row {
+    Item(usize),
+    Group(usize),
+}
+
+impl Row {
+    fn item(self) -> Option<usize> {
+        match self {
+            Row::Item(i) => Some(i),
+            Row::Group(_) => None,
+        }
+    }
+}
+
+struct Group {
+    title: String,
+    /// Indices into `items` of the group's members, in item order
+    items: Vec<usize>,
+    collapsed: bool,
+}
+
+/// Formatted rows, filled in as items are first shown or searched so long
+/// lists only format what's actually looked at.
+struct RowCache {
+    lines: Vec<Option<Line<'static>>>,
+    columns: Vec<Option<Line<'static>>>,
+    /// The search text of every item, built on the first search
+    texts: Vec<String>,
+}
+
+impl RowCache {
+    fn new(len: usize) -> Self {
+        Self {
+            lines: vec![None; len],
+            columns: vec![None; len],
+            texts: Vec::new(),
+        }
+    }
+}
+
 struct ConfirmButtons {
     confirm_label: String,
     cancel_label: String,
@@ -21,17 +117,50 @@ pub(crate) struct SelectList<T> {
     items: Vec<T>,
     state: ListState,
     header: String,
-    display_fn: fn(&T) -> String,
+    display_fn: RowFn<T>,
+    column_fn: Option<RowFn<T>>,
+    /// Columns to lay the rows out in instead of `display_fn`
+    table: Option<Table<T>>,
+    /// Groups the items are shown under, if any
+    groups: Vec<Group>,
+    /// What the groups were made from, kept to place items fed in later
+    group_fn: Option<GroupFn<T>>,
+    /// The index into `groups` of each item
+    group_of: Vec<usize>,
+    /// The index into `groups` of each group's title
+    group_titles: HashMap<String, usize>,
+    preview: Option<Preview<T>>,
     confirm: Option<ConfirmButtons>,
     toggleable: bool,
+    fullscreen: bool,
     selected: Vec<bool>,
+    keymap: Keymap,
+    theme: Theme,
+    /// The rows shown for the current query, in list order
+    visible: Vec<Row>,
+    /// Matched character positions for each visible row
+    matches: Vec<Vec<usize>>,
+    query: String,
+    searching: bool,
+    /// The item under the cursor when the search started
+    saved_cursor: Option<usize>,
+    /// The item a visual range was started from
+    anchor: Option<usize>,
+    areas: HitAreas,
+    cache: RowCache,
+    /// Where another thread sends updates while the list is on screen
+    feed: Option<Receiver<Feed<T>>>,
+    /// The latest status fed in, see [`Feed::Status`]
+    status: Option<String>,
 }
 
 impl<T> SelectList<T> {
-    pub(crate) fn new(
+    /// Create a list rendering each item with `display_fn`, which can return
+    /// anything that converts into a styled [`Line`], including plain strings.
+    pub(crate) fn new<L: Into<Line<'static>>>(
         items: Vec<T>,
         header: impl Into<String>,
-        display_fn: fn(&T) -> String,
+        display_fn: impl Fn(&T) -> L + 'static,
     ) -> Self {
         let mut state = ListState::default();
         let len = items.len();
@@ -42,13 +171,42 @@ impl<T> SelectList<T> {
             items,
             state,
             header: header.into(),
-            display_fn,
+            display_fn: Box::new(move |item| display_fn(item).into()),
+            column_fn: None,
+            table: None,
+            groups: Vec::new(),
+            group_fn: None,
+            group_of: Vec::new(),
+            group_titles: HashMap::new(),
+            preview: None,
             confirm: None,
             toggleable: false,
+            fullscreen: false,
             selected: vec![true; len],
+            keymap: Keymap::current(),
+            theme: Theme::current(),
+            visible: (0..len).map(Row::Item).collect(),
+            matches: vec![Vec::new(); len],
+            query: String::new(),
+            searching: false,
+            saved_cursor: None,
+            anchor: None,
+            areas: HitAreas::default(),
+            cache: RowCache::new(len),
+            feed: None,
+            status: None,
         }
     }
 
+    /// Create a list laid out as a table of `columns`, which can be sorted
+    /// by column from the keyboard.
+    pub(crate) fn table(items: Vec<T>, header: impl Into<String>, columns: Vec<Column<T>>) -> Self {
+        let table = Table::new(columns, &items);
+        let mut list = Self::new(items, header, |_: &T| "");
+        list.table = Some(table);
+        list
+    }
+
     pub(crate) fn with_confirm(
         mut self,
         confirm_label: impl Into<String>,
@@ -62,11 +220,64 @@ impl<T> SelectList<T> {
         self
     }
 
+    /// Render an extra right-aligned column for each row, e.g. counts or
+    /// dimmed metadata. It isn't searched when filtering.
+    pub(crate) fn with_column<L: Into<Line<'static>>>(
+        mut self,
+        column_fn: impl Fn(&T) -> L + 'static,
+    ) -> Self {
+        self.column_fn = Some(Box::new(move |item| column_fn(item).into()));
+        self
+    }
+
+    /// Show a pane with more detail about the item under the cursor, such as
+    /// its full multi-line content. It scrolls separately from the list.
+    pub(crate) fn with_preview<X: Into<Text<'static>>>(
+        mut self,
+        position: PreviewPosition,
+        preview_fn: impl Fn(&T) -> X + 'static,
+    ) -> Self {
+        self.preview = Some(Preview {
+            preview_fn: Box::new(move |item| preview_fn(item).into()),
+            position,
+            scroll: 0,
+            item: None,
+        });
+        self
+    }
+
+    /// Show the items under collapsible headers, grouping those that
+    /// `group_fn` gives the same title. Groups are listed in the order their
+    /// first item appears, and toggling a header toggles the whole group.
+    pub(crate) fn with_groups(mut self, group_fn: impl Fn(&T) -> String + 'static) -> Self {
+        self.group_fn = Some(Box::new(group_fn));
+        self.assign_groups(0);
+        // Start on the first header rather than following the first item
+        self.state.select(None);
+        self.apply_filter();
+        self
+    }
+
+    /// Keep taking items and status updates from `feed` while the list is on
+    /// screen, e.g. search results as they're found. The list shows right
+    /// away, even before the first items arrive.
+    pub(crate) fn with_feed(mut self, feed: Receiver<Feed<T>>) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
     pub(crate) fn with_toggleable(mut self) -> Self {
         self.toggleable = true;
         self
     }
 
+    /// Draw on the alternate screen using the whole terminal, instead of
+    /// inline below the prompt.
+    pub(crate) fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
     pub(crate) fn set_selected(&mut self, index: usize, value: bool) {
         if let Some(s) = self.selected.get_mut(index) {
             *s = value;
@@ -85,205 +296,1699 @@ impl<T> SelectList<T> {
             .collect()
     }
 
-    pub(crate) fn run(&mut self, terminal: &mut InlineTerminal) -> crate::Result<SelectResult> {
-        let has_buttons = self.confirm.is_some();
+    /// Run a single-choice list, returning the picked item or `None` when
+    /// it was cancelled.
+    pub(crate) fn pick_one(&mut self, height: u16, width: u16) -> crate::Result<Option<&T>> {
+        match self.prompt(height, width)? {
+            SelectResult::Picked(i) => Ok(Some(&self.items[i])),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn unselected_items(&self) -> Vec<&T> {
+        self.items
+            .iter()
+            .zip(self.selected.iter())
+            .filter_map(|(item, sel)| if *sel { None } else { Some(item) })
+            .collect()
+    }
+
+    /// Run the list in an inline terminal of at most the given size (or
+    /// fullscreen, see [`Self::with_fullscreen`]), or as a plain numbered
+    /// prompt on stdin/stderr when stdout isn't a terminal.
+    pub(crate) fn prompt(&mut self, height: u16, width: u16) -> crate::Result<SelectResult> {
+        if !is_interactive() {
+            // The plain prompt can't update, so it waits for the whole feed
+            for feed in self.feed.take().into_iter().flatten() {
+                self.apply_feed(feed);
+            }
+            return self.run_plain(&mut std::io::stdin().lock(), &mut std::io::stderr());
+        }
+
+        let mut terminal = if self.fullscreen {
+            InlineTerminal::fullscreen()?
+        } else {
+            InlineTerminal::new(height, width)?
+        };
+        let result = self.run(&mut terminal)?;
+        terminal.cleanup()?;
+        Ok(result)
+    }
+
+    /// Run the non-interactive fallback: print the numbered items and read
+    /// choices line by line from `input`.
+    ///
+    /// Toggleable lists accept numbers and ranges (`1 3-5`) to toggle,
+    /// `all`, `none` or `invert`, an empty line to confirm and `q` to cancel.
+    /// Plain lists accept a single number to pick that item. The list is
+    /// printed once, and again for `l`.
+    pub(crate) fn run_plain(
+        &mut self,
+        input: &mut impl std::io::BufRead,
+        output: &mut impl std::io::Write,
+    ) -> crate::Result<SelectResult> {
+        let mut read_any = false;
+        let table = self.table.as_ref().map(|t| t.plain_rows(&self.items));
+        self.print_plain(table.as_ref(), output)?;
 
         loop {
-            let header = &self.header;
-            let display_fn = self.display_fn;
-            let items = &self.items;
-            let state = &mut self.state;
-            let confirm = &self.confirm;
-
-            terminal.draw(|f| {
-                let area = f.area();
-
-                let mut constraints = vec![
-                    Constraint::Length(1),
-                    Constraint::Length(1),
-                    Constraint::Min(1),
-                ];
-
-                if has_buttons {
-                    constraints.push(Constraint::Length(1));
-                    constraints.push(Constraint::Length(1));
-                } else {
-                    constraints.push(Constraint::Length(1));
+            if self.toggleable {
+                write!(
+                    output,
+                    "Numbers to toggle (e.g. 1 3-5), all, none, invert, l to list, Enter to confirm, q to cancel: "
+                )?;
+            } else {
+                write!(output, "Number to pick, l to list, q to cancel: ")?;
+            }
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(output)?;
+                if !read_any {
+                    return Err(TerminalErrors::NoInput.into());
                 }
+                return Ok(SelectResult::Cancelled);
+            }
+            read_any = true;
 
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(constraints)
-                    .split(area);
-
-                let header_line = Line::from(Span::styled(
-                    header.as_str(),
-                    Style::default().fg(Color::Yellow).bold(),
-                ));
-
-                f.render_widget(Paragraph::new(header_line), chunks[0]);
-                f.render_widget(Paragraph::new(""), chunks[1]);
-
-                let toggleable = self.toggleable;
-                let selected = &self.selected;
-
-                let list_items: Vec<ListItem> = items
-                    .iter()
-                    .enumerate()
-                    .map(|(i, item)| {
-                        let text = display_fn(item);
-                        if toggleable {
-                            let (check, check_style, text_style) = if selected[i] {
-                                ("● ", Style::default().fg(Color::Green), Style::default())
-                            } else {
-                                ("○ ", Style::default().fg(Color::Gray).dim(), Style::default().fg(Color::Gray).dim())
-                            };
-                            ListItem::new(Line::from(vec![
-                                Span::styled(check, check_style),
-                                Span::styled(text, text_style),
-                            ]))
-                        } else {
-                            ListItem::new(text)
-                        }
-                    })
-                    .collect();
+            let line = line.trim();
+            if line.eq_ignore_ascii_case("q") {
+                return Ok(SelectResult::Cancelled);
+            }
+            if line.eq_ignore_ascii_case("l") {
+                self.print_plain(table.as_ref(), output)?;
+                continue;
+            }
 
-                let list = List::new(list_items)
-                    .highlight_style(Style::default().bg(Color::DarkGray).bold())
-                    .highlight_symbol(" > ");
+            if self.toggleable {
+                let bulk = match line.to_lowercase().as_str() {
+                    "" => return Ok(self.many()),
+                    "all" => {
+                        self.selected.fill(true);
+                        true
+                    }
+                    "none" => {
+                        self.selected.fill(false);
+                        true
+                    }
+                    "invert" => {
+                        self.selected.iter_mut().for_each(|s| *s = !*s);
+                        true
+                    }
+                    _ => false,
+                };
+                // Bulk changes touch every row, so only the count is shown
+                if bulk {
+                    writeln!(output, "{}", self.selected_count())?;
+                    continue;
+                }
+            }
 
-                f.render_stateful_widget(list, chunks[2], state);
+            let Some(indices) = parse_selection(line, self.items.len()) else {
+                writeln!(output, "Could not understand `{line}`")?;
+                continue;
+            };
 
-                if let Some(buttons) = confirm {
-                    f.render_widget(Paragraph::new(""), chunks[3]);
+            if self.toggleable {
+                for &i in &indices {
+                    self.selected[i] = !self.selected[i];
+                }
+                for i in indices {
+                    writeln!(output, "{}", self.plain_row(i, table.as_ref()))?;
+                }
+                writeln!(output, "{}", self.selected_count())?;
+            } else if let [i] = indices[..] {
+                self.state
+                    .select(self.visible.iter().position(|&r| r == Row::Item(i)));
+                return Ok(SelectResult::Picked(i));
+            } else {
+                writeln!(output, "Pick exactly one item")?;
+            }
+        }
+    }
 
-                    let confirm_style = if buttons.selected_confirm {
-                        Style::default().bg(Color::DarkGray).fg(Color::Green).bold()
-                    } else {
-                        Style::default().fg(Color::DarkGray)
-                    };
+    /// Print the header and every row, numbered, for [`Self::run_plain`].
+    fn print_plain(
+        &self,
+        table: Option<&(String, Vec<String>)>,
+        output: &mut impl std::io::Write,
+    ) -> crate::Result<()> {
+        writeln!(output, "{}", self.header)?;
+        if self.toggleable {
+            writeln!(output, "{}", self.selected_count())?;
+        }
+        if let Some((header, _)) = table {
+            let indent = if self.toggleable { 10 } else { 6 };
+            writeln!(output, "{:indent$}{header}", "")?;
+        }
+        for row in self.plain_order() {
+            match row {
+                Row::Item(i) => writeln!(output, "{}", self.plain_row(i, table))?,
+                Row::Group(g) => writeln!(output, "      {}", self.group_label(g))?,
+            }
+        }
+        Ok(())
+    }
 
-                    let cancel_style = if !buttons.selected_confirm {
-                        Style::default().bg(Color::DarkGray).fg(Color::Red).bold()
-                    } else {
-                        Style::default().fg(Color::DarkGray)
-                    };
+    /// The numbered row for item `i`, with its checkbox in toggleable lists.
+    fn plain_row(&self, i: usize, table: Option<&(String, Vec<String>)>) -> String {
+        let item = &self.items[i];
+        let mut text = match table {
+            Some((_, rows)) => rows[i].clone(),
+            None => (self.display_fn)(item).to_string(),
+        };
+        if let Some(column_fn) = &self.column_fn {
+            text.push_str(&format!("  ({})", column_fn(item)));
+        }
+        if self.toggleable {
+            let check = if self.selected[i] { "x" } else { " " };
+            format!("{:>4}. [{check}] {text}", i + 1)
+        } else {
+            format!("{:>4}. {text}", i + 1)
+        }
+    }
 
-                    let button_line = Line::from(vec![
-                        Span::raw("  "),
-                        Span::styled(format!(" {} ", buttons.confirm_label), confirm_style),
-                        Span::raw("  "),
-                        Span::styled(format!(" {} ", buttons.cancel_label), cancel_style),
-                        Span::styled(
-                            if toggleable {
-                                "  (← → select, Space toggle, Enter confirm)"
-                            } else {
-                                "  (← → select, Enter confirm)"
-                            },
-                            Style::default().fg(Color::DarkGray),
-                        ),
-                    ]);
-                    f.render_widget(Paragraph::new(button_line), chunks[4]);
-                } else {
-                    let hint = if toggleable {
-                        "  (↑↓ navigate, Space toggle, Enter confirm, Esc cancel)"
-                    } else {
-                        "  (↑↓ navigate, Enter confirm, Esc cancel)"
-                    };
-                    f.render_widget(
-                        Paragraph::new(Span::styled(hint, Style::default().fg(Color::DarkGray))),
-                        chunks[3],
-                    );
+    /// Put the items from `from` on into their groups, making new groups
+    /// for titles not seen before.
+    fn assign_groups(&mut self, from: usize) {
+        let Some(group_fn) = &self.group_fn else {
+            return;
+        };
+        for i in from..self.items.len() {
+            let title = group_fn(&self.items[i]);
+            let g = match self.group_titles.get(&title) {
+                Some(&g) => g,
+                None => {
+                    self.group_titles.insert(title.clone(), self.groups.len());
+                    self.groups.push(Group {
+                        title,
+                        items: Vec::new(),
+                        collapsed: false,
+                    });
+                    self.groups.len() - 1
                 }
-            })?;
+            };
+            self.groups[g].items.push(i);
+            self.group_of.push(g);
+        }
+    }
 
-            if event::poll(std::time::Duration::from_millis(100))?
-                && let Event::Key(key) = event::read()?
-            {
-                if key.kind != KeyEventKind::Press {
-                    continue;
+    /// Apply something fed in from another thread.
+    fn apply_feed(&mut self, feed: Feed<T>) {
+        match feed {
+            Feed::Items(items) => {
+                let from = self.items.len();
+                if let Some(table) = &mut self.table {
+                    table.measure(&items);
                 }
+                self.items.extend(items);
+
+                let len = self.items.len();
+                self.selected.resize(len, true);
+                self.cache.lines.resize(len, None);
+                self.cache.columns.resize(len, None);
+                // Only extend the search texts once a search has built them
+                if !self.cache.texts.is_empty() {
+                    let texts: Vec<_> = self.items[from..]
+                        .iter()
+                        .map(|item| self.search_text(item))
+                        .collect();
+                    self.cache.texts.extend(texts);
+                }
+                self.assign_groups(from);
+
+                // New items shouldn't knock the list out of range mode
+                let anchor = self.anchor;
+                self.apply_filter();
+                self.anchor = anchor;
+            }
+            Feed::Status(status) => self.status = status,
+        }
+    }
+
+    /// The text search matches an item against.
+    fn search_text(&self, item: &T) -> String {
+        match &self.table {
+            Some(table) => table.text(item),
+            None => (self.display_fn)(item).to_string(),
+        }
+    }
 
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => {
-                        return Ok(SelectResult::Cancelled);
+    /// The row under the cursor.
+    fn cursor_row(&self) -> Option<Row> {
+        self.state
+            .selected()
+            .and_then(|c| self.visible.get(c).copied())
+    }
+
+    /// The index into `items` of the row under the cursor, if it's an item.
+    fn cursor_item(&self) -> Option<usize> {
+        self.cursor_row().and_then(Row::item)
+    }
+
+    /// Recompute which rows are visible for the current query, keeping the
+    /// cursor on the same row when it's still visible, or on the header of
+    /// the group it was folded into.
+    fn apply_filter(&mut self) {
+        let current = self.cursor_row();
+        self.anchor = None;
+
+        let mut found: Vec<(usize, Vec<usize>)> = if self.query.is_empty() {
+            (0..self.items.len()).map(|i| (i, Vec::new())).collect()
+        } else {
+            if self.cache.texts.len() != self.items.len() {
+                self.cache.texts = self.items.iter().map(|i| self.search_text(i)).collect();
+            }
+
+            let matcher = SkimMatcherV2::default();
+            self.cache
+                .texts
+                .iter()
+                .enumerate()
+                .filter_map(|(i, text)| {
+                    let (_, indices) = matcher.fuzzy_indices(text, &self.query)?;
+                    Some((i, indices))
+                })
+                .collect()
+        };
+
+        if let Some(table) = &self.table {
+            table.sort_rows(&self.items, &mut found);
+        }
+
+        (self.visible, self.matches) = if self.groups.is_empty() {
+            found.into_iter().map(|(i, m)| (Row::Item(i), m)).collect()
+        } else {
+            self.group_rows(found).into_iter().unzip()
+        };
+
+        let position = |row: Row| self.visible.iter().position(|&r| r == row);
+        let cursor = current
+            .and_then(|current| {
+                position(current).or_else(|| match current {
+                    Row::Item(i) if !self.groups.is_empty() => {
+                        position(Row::Group(self.group_of[i]))
                     }
+                    _ => None,
+                })
+            })
+            .or(if self.visible.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.state.select(cursor);
+    }
+
+    /// Put each found item under its group's header, leaving out the items
+    /// of collapsed groups unless there's a search to show them for.
+    fn group_rows(&self, found: Vec<(usize, Vec<usize>)>) -> Vec<(Row, Vec<usize>)> {
+        let mut members = vec![Vec::new(); self.groups.len()];
+        for (i, indices) in found {
+            members[self.group_of[i]].push((Row::Item(i), indices));
+        }
 
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        let cursor = self.state.selected().unwrap_or(0);
-                        if cursor > 0 {
-                            self.state.select(Some(cursor - 1));
+        let mut rows = Vec::new();
+        for (g, members) in members.into_iter().enumerate() {
+            if members.is_empty() {
+                continue;
+            }
+            rows.push((Row::Group(g), Vec::new()));
+            if !self.groups[g].collapsed || !self.query.is_empty() {
+                rows.extend(members);
+            }
+        }
+        rows
+    }
+
+    /// Every row in order for the plain prompt, which has no search or
+    /// folding.
+    fn plain_order(&self) -> Vec<Row> {
+        if self.groups.is_empty() {
+            return (0..self.items.len()).map(Row::Item).collect();
+        }
+        self.groups
+            .iter()
+            .enumerate()
+            .flat_map(|(g, group)| {
+                std::iter::once(Row::Group(g)).chain(group.items.iter().map(|&i| Row::Item(i)))
+            })
+            .collect()
+    }
+
+    /// A group's title with how many of its items are selected.
+    fn group_label(&self, g: usize) -> String {
+        let group = &self.groups[g];
+        if self.toggleable {
+            let selected = group.items.iter().filter(|&&i| self.selected[i]).count();
+            format!("{} ({selected}/{})", group.title, group.items.len())
+        } else {
+            format!("{} ({})", group.title, group.items.len())
+        }
+    }
+
+    /// Select or deselect a whole group: everything, unless it already is.
+    fn toggle_group(&mut self, g: usize) {
+        let items = &self.groups[g].items;
+        let value = !items.iter().all(|&i| self.selected[i]);
+        for &i in items {
+            self.selected[i] = value;
+        }
+    }
+
+    /// Collapse or expand the group under the cursor.
+    fn fold(&mut self) {
+        let group = match self.cursor_row() {
+            Some(Row::Group(g)) => g,
+            Some(Row::Item(i)) if !self.groups.is_empty() => self.group_of[i],
+            _ => return,
+        };
+        self.groups[group].collapsed = !self.groups[group].collapsed;
+        self.apply_filter();
+    }
+
+    /// Leave search mode and drop the query, putting the cursor back where it
+    /// was before the search started.
+    fn clear_search(&mut self) {
+        self.searching = false;
+        self.query.clear();
+        self.apply_filter();
+
+        if let Some(saved) = self.saved_cursor.take() {
+            self.state
+                .select(self.visible.iter().position(|&r| r == Row::Item(saved)));
+        }
+    }
+
+    pub(crate) fn run<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut InlineTerminal<B>,
+    ) -> crate::Result<SelectResult> {
+        let feed = self.feed.take();
+        app::run(self, terminal, feed.as_ref())
+    }
+
+    /// Apply a key press or mouse event, returning the result once it
+    /// answers the list.
+    fn handle_event(&mut self, event: Event) -> Option<SelectResult> {
+        let action = match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press && is_interrupt(&key) => {
+                return Some(SelectResult::Cancelled);
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if self.searching {
+                    match key.code {
+                        KeyCode::Esc => self.clear_search(),
+                        KeyCode::Enter => {
+                            self.searching = false;
+                            if self.query.is_empty() {
+                                self.clear_search();
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            if self.query.pop().is_none() {
+                                self.clear_search();
+                            } else {
+                                self.apply_filter();
+                            }
+                        }
+                        KeyCode::Up => self.move_cursor_up(),
+                        KeyCode::Down => self.move_cursor_down(),
+                        KeyCode::Char(c) => {
+                            self.query.push(c);
+                            self.apply_filter();
                         }
+                        _ => {}
                     }
+                    return None;
+                }
 
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        let cursor = self.state.selected().unwrap_or(0);
-                        if cursor < self.items.len().saturating_sub(1) {
-                            self.state.select(Some(cursor + 1));
-                        }
+                self.keymap.action(&key, &self.actions())
+            }
+            Event::Mouse(mouse) => self.mouse_action(mouse),
+            _ => None,
+        };
+
+        if let Some(action) = action {
+            match action {
+                // Cancelling backs out of a visual range, then drops the
+                // filter, before cancelling the list itself
+                Action::Cancel if self.anchor.is_some() => self.anchor = None,
+                Action::Cancel if !self.query.is_empty() => self.clear_search(),
+                Action::Cancel => return Some(SelectResult::Cancelled),
+
+                Action::Search => {
+                    self.anchor = None;
+                    if self.query.is_empty() {
+                        self.saved_cursor = self.cursor_item();
                     }
+                    self.searching = true;
+                }
+
+                Action::Up => self.move_cursor_up(),
+                Action::Down => self.move_cursor_down(),
 
-                    KeyCode::Home | KeyCode::Char('g') => {
-                        self.state.select(Some(0));
+                Action::Top if !self.visible.is_empty() => {
+                    self.state.select(Some(0));
+                }
+
+                Action::Bottom if !self.visible.is_empty() => {
+                    self.state.select(Some(self.visible.len() - 1));
+                }
+
+                Action::FocusConfirm | Action::FocusCancel | Action::SwitchButton => {
+                    if let Some(ref mut buttons) = self.confirm {
+                        buttons.selected_confirm = match action {
+                            Action::FocusConfirm => true,
+                            Action::FocusCancel => false,
+                            _ => !buttons.selected_confirm,
+                        };
                     }
+                }
 
-                    KeyCode::End | KeyCode::Char('G') => {
-                        if !self.items.is_empty() {
-                            self.state.select(Some(self.items.len() - 1));
-                        }
+                Action::PreviewUp => {
+                    if let Some(preview) = &mut self.preview {
+                        preview.scroll = preview.scroll.saturating_sub(1);
                     }
+                }
 
-                    KeyCode::Left | KeyCode::Char('h') if has_buttons => {
-                        if let Some(ref mut buttons) = self.confirm {
-                            buttons.selected_confirm = true;
-                        }
+                Action::PreviewDown => {
+                    let cursor = self.cursor_item();
+                    let pane = self.areas.preview.unwrap_or_default();
+                    if let Some(preview) = &mut self.preview {
+                        // The pane wraps its text, inside the border and
+                        // padding on its left when it's on the right
+                        let width = match preview.position {
+                            PreviewPosition::Right => pane.width.saturating_sub(2),
+                            PreviewPosition::Bottom => pane.width,
+                        };
+                        let max = cursor
+                            .map(|i| wrapped_height(&(preview.preview_fn)(&self.items[i]), width))
+                            .unwrap_or(0)
+                            .saturating_sub(1);
+                        preview.scroll = (preview.scroll + 1).min(max as u16);
                     }
+                }
 
-                    KeyCode::Right | KeyCode::Char('l') if has_buttons => {
-                        if let Some(ref mut buttons) = self.confirm {
-                            buttons.selected_confirm = false;
-                        }
+                Action::Toggle | Action::VisualRange if self.anchor.is_some() => {
+                    self.toggle_range();
+                }
+
+                Action::Toggle => match self.cursor_row() {
+                    Some(Row::Item(i)) => self.selected[i] = !self.selected[i],
+                    Some(Row::Group(g)) => self.toggle_group(g),
+                    None => {}
+                },
+
+                Action::Fold => self.fold(),
+
+                Action::VisualRange => self.anchor = self.cursor_item(),
+                Action::SelectAll => self.selected.fill(true),
+                Action::SelectNone => self.selected.fill(false),
+                Action::InvertSelection => self.selected.iter_mut().for_each(|s| *s = !*s),
+
+                Action::ToggleFiltered => {
+                    let items: Vec<usize> = self.visible.iter().filter_map(|r| r.item()).collect();
+                    let all_selected = items.iter().all(|&i| self.selected[i]);
+                    for i in items {
+                        self.selected[i] = !all_selected;
                     }
+                }
 
-                    KeyCode::Tab if has_buttons => {
-                        if let Some(ref mut buttons) = self.confirm {
-                            buttons.selected_confirm = !buttons.selected_confirm;
+                Action::SortNext | Action::SortReverse => {
+                    if let Some(table) = &mut self.table {
+                        match action {
+                            Action::SortNext => table.sort_next(),
+                            _ => table.reverse_sort(),
                         }
                     }
+                    self.apply_filter();
+                }
 
-                    KeyCode::Char('y') | KeyCode::Char('Y') if has_buttons => {
-                        return Ok(SelectResult::Confirmed);
+                // Without checkboxes there's nothing else for Enter to do
+                // on a header, so it folds the group
+                Action::Confirm
+                    if !self.toggleable && matches!(self.cursor_row(), Some(Row::Group(_))) =>
+                {
+                    self.fold()
+                }
+
+                Action::Confirm => {
+                    let confirmed = self
+                        .confirm
+                        .as_ref()
+                        .map(|b| b.selected_confirm)
+                        .unwrap_or(true);
+                    if !confirmed {
+                        return Some(SelectResult::Cancelled);
+                    }
+                    if self.toggleable {
+                        return Some(self.many());
                     }
+                    // Nothing to pick while a search matches nothing
+                    if let Some(i) = self.cursor_item() {
+                        return Some(SelectResult::Picked(i));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn render(&mut self, f: &mut Frame) {
+        let has_buttons = self.confirm.is_some();
+        let cursor = self.cursor_item();
+        if let Some(preview) = &mut self.preview
+            && preview.item != cursor
+        {
+            preview.item = cursor;
+            preview.scroll = 0;
+        }
+
+        let hint = self.hint();
+        let range = self.visual_range();
+        let count = self.selected_count();
+        let header = &self.header;
+        let display_fn = &self.display_fn;
+        let column_fn = &self.column_fn;
+        let table = &self.table;
+        let items = &self.items;
+        let groups = &self.groups;
+        let visible = &self.visible;
+        let matches = &self.matches;
+        let state = &mut self.state;
+        let confirm = &self.confirm;
+        let query = &self.query;
+        let searching = self.searching;
+        let theme = &self.theme;
+        let preview = &self.preview;
+        let areas = &mut self.areas;
+        let cache = &mut self.cache;
+        let status = &self.status;
+
+        let area = f.area();
+
+        let mut constraints = vec![
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(1),
+        ];
+
+        if has_buttons {
+            constraints.push(Constraint::Length(1));
+            constraints.push(Constraint::Length(1));
+        } else {
+            constraints.push(Constraint::Length(1));
+        }
 
-                    KeyCode::Char('n') | KeyCode::Char('N') if has_buttons => {
-                        return Ok(SelectResult::Cancelled);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(area);
+
+        let header_line = Line::from(Span::styled(header.as_str(), theme.header));
+
+        f.render_widget(Paragraph::new(header_line), chunks[0]);
+
+        if searching || !query.is_empty() {
+            let mut search_line = vec![Span::styled("/", theme.accent), Span::raw(query.as_str())];
+            if searching {
+                search_line.push(Span::styled("█", theme.accent));
+            }
+            let found = visible.iter().filter(|r| r.item().is_some()).count();
+            search_line.push(Span::styled(
+                format!("  {found}/{} matches", items.len()),
+                theme.muted,
+            ));
+            if let Some(status) = status {
+                search_line.push(Span::styled(format!("  {status}"), theme.muted));
+            }
+            f.render_widget(Paragraph::new(Line::from(search_line)), chunks[1]);
+        } else if let Some(status) = status {
+            f.render_widget(
+                Paragraph::new(Span::styled(status.as_str(), theme.muted)),
+                chunks[1],
+            );
+        } else {
+            f.render_widget(Paragraph::new(""), chunks[1]);
+        }
+
+        let toggleable = self.toggleable;
+        let selected = &self.selected;
+
+        let list_area = match preview {
+            Some(preview) => {
+                let (direction, block) = match preview.position {
+                    PreviewPosition::Right => (
+                        Direction::Horizontal,
+                        Block::new()
+                            .borders(Borders::LEFT)
+                            .padding(Padding::left(1)),
+                    ),
+                    PreviewPosition::Bottom => {
+                        (Direction::Vertical, Block::new().borders(Borders::TOP))
                     }
+                };
+                let panes = Layout::default()
+                    .direction(direction)
+                    .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(chunks[2]);
+
+                let text = cursor
+                    .map(|i| (preview.preview_fn)(&items[i]))
+                    .unwrap_or_default();
+                let pane = Paragraph::new(text)
+                    .block(block.border_style(theme.muted))
+                    .wrap(Wrap { trim: false })
+                    .scroll((preview.scroll, 0));
+                f.render_widget(pane, panes[1]);
+                areas.preview = Some(panes[1]);
+
+                panes[0]
+            }
+            None => chunks[2],
+        };
 
-                    KeyCode::Char(' ') if self.toggleable => {
-                        if let Some(cursor) = self.state.selected() {
-                            self.selected[cursor] = !self.selected[cursor];
+        // Room left for row content once the highlight symbol is drawn
+        let row_width = list_area.width.saturating_sub(3) as usize;
+
+        // Items sit under their group's header
+        let indent = if groups.is_empty() { 0 } else { 2 };
+
+        // Tables give up their first row to the column titles
+        let (list_area, widths) = match table {
+            Some(table) => {
+                let checkbox = if toggleable { 2 } else { 0 } + indent;
+                let widths = table.widths(row_width.saturating_sub(checkbox));
+                let mut titles = table.header(&widths, theme);
+                titles.spans.insert(0, Span::raw(" ".repeat(3 + checkbox)));
+                let [titles_area, rows_area] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(list_area);
+                f.render_widget(Paragraph::new(titles), titles_area);
+                (rows_area, widths)
+            }
+            None => (list_area, Vec::new()),
+        };
+
+        // Only the rows in view are formatted, scrolling the window just
+        // far enough to keep the cursor in it
+        let height = (list_area.height as usize).max(1);
+        let cursor_row = state.selected();
+        let mut offset = state.offset().min(visible.len().saturating_sub(height));
+        if let Some(row) = cursor_row {
+            offset = offset.clamp((row + 1).saturating_sub(height), row);
+        }
+        *state.offset_mut() = offset;
+        let window = offset..(offset + height).min(visible.len());
+
+        let list_items: Vec<ListItem> = visible[window.clone()]
+            .iter()
+            .zip(matches[window].iter())
+            .enumerate()
+            .map(|(n, (&row, indices))| {
+                let i = match row {
+                    Row::Item(i) => i,
+                    Row::Group(g) => {
+                        return group_item(&groups[g], toggleable, selected, theme);
+                    }
+                };
+                let row = offset + n;
+                let deselected = toggleable && !selected[i];
+                let mut spans = match table {
+                    Some(table) => {
+                        let mut spans = table.row(&items[i], &widths, indices, theme.matched);
+                        if deselected {
+                            for span in &mut spans {
+                                span.style = span.style.patch(theme.unchecked);
+                            }
+                        }
+                        spans
+                    }
+                    None => {
+                        let mut line = cache.lines[i]
+                            .get_or_insert_with(|| display_fn(&items[i]))
+                            .clone();
+                        if deselected {
+                            line = line.patch_style(theme.unchecked);
                         }
+                        highlight(line, indices, theme.matched)
                     }
+                };
+                if toggleable {
+                    let (check, check_style) = if selected[i] {
+                        ("● ", theme.checked)
+                    } else {
+                        ("○ ", theme.unchecked)
+                    };
+                    spans.insert(0, Span::styled(check, check_style));
+                }
+                if indent > 0 {
+                    spans.insert(0, Span::raw(" ".repeat(indent)));
+                }
+                if let Some(column_fn) = column_fn {
+                    let mut column = cache.columns[i]
+                        .get_or_insert_with(|| column_fn(&items[i]))
+                        .clone();
+                    if deselected {
+                        column = column.patch_style(theme.unchecked);
+                    }
+                    let used: usize = spans.iter().map(|s| s.width()).sum();
+                    let pad = row_width.saturating_sub(used + column.width()).max(1);
+                    spans.push(Span::raw(" ".repeat(pad)));
+                    spans.extend(column.spans);
+                }
+                let item = ListItem::new(Line::from(spans));
+                match &range {
+                    Some(range) if range.contains(&row) => item.style(theme.range),
+                    _ => item,
+                }
+            })
+            .collect();
 
-                    KeyCode::Enter => {
-                        if has_buttons {
-                            let confirmed = self
-                                .confirm
-                                .as_ref()
-                                .map(|b| b.selected_confirm)
-                                .unwrap_or(false);
-                            return if confirmed {
-                                Ok(SelectResult::Confirmed)
-                            } else {
-                                Ok(SelectResult::Cancelled)
-                            };
-                        } else {
-                            return Ok(SelectResult::Confirmed);
+        let list = List::new(list_items)
+            .highlight_style(theme.highlight)
+            .highlight_symbol(" > ");
+
+        // The list only holds the window, so its cursor is relative to it
+        let mut window_state =
+            ListState::default().with_selected(cursor_row.map(|row| row - offset));
+        f.render_stateful_widget(list, list_area, &mut window_state);
+        areas.list = list_area;
+
+        if let Some(buttons) = confirm {
+            f.render_widget(Paragraph::new(""), chunks[3]);
+
+            let (confirm_style, cancel_style) = if buttons.selected_confirm {
+                (theme.confirm, theme.muted)
+            } else {
+                (theme.muted, theme.cancel)
+            };
+
+            let confirm_button =
+                Span::styled(format!(" {} ", buttons.confirm_label), confirm_style);
+            let cancel_button = Span::styled(format!(" {} ", buttons.cancel_label), cancel_style);
+            let confirm_width = confirm_button.width() as u16;
+            let row = chunks[4];
+            areas.confirm = Some(Rect::new(row.x + 2, row.y, confirm_width, 1));
+            areas.cancel = Some(Rect::new(
+                row.x + 4 + confirm_width,
+                row.y,
+                cancel_button.width() as u16,
+                1,
+            ));
+
+            let mut button_line = Line::from(vec![
+                Span::raw("  "),
+                confirm_button,
+                Span::raw("  "),
+                cancel_button,
+            ]);
+            if toggleable {
+                button_line.push_span(Span::styled(format!("  {count}"), theme.accent));
+            }
+            button_line.push_span(Span::styled(format!("  {hint}"), theme.muted));
+            f.render_widget(Paragraph::new(button_line), chunks[4]);
+        } else {
+            let mut hint_line = Line::default();
+            if toggleable {
+                hint_line.push_span(Span::styled(format!("  {count}"), theme.accent));
+            }
+            hint_line.push_span(Span::styled(format!("  {hint}"), theme.muted));
+            f.render_widget(Paragraph::new(hint_line), chunks[3]);
+        }
+    }
+
+    /// The actions that apply to the list in its current configuration.
+    fn actions(&self) -> Vec<Action> {
+        let mut actions = vec![
+            Action::Cancel,
+            Action::Search,
+            Action::Up,
+            Action::Down,
+            Action::Top,
+            Action::Bottom,
+        ];
+        if self.confirm.is_some() {
+            actions.extend([
+                Action::FocusConfirm,
+                Action::FocusCancel,
+                Action::SwitchButton,
+            ]);
+        }
+        if self.toggleable {
+            actions.extend([
+                Action::Toggle,
+                Action::VisualRange,
+                Action::SelectAll,
+                Action::SelectNone,
+                Action::InvertSelection,
+                Action::ToggleFiltered,
+            ]);
+        }
+        if self.preview.is_some() {
+            actions.extend([Action::PreviewUp, Action::PreviewDown]);
+        }
+        if self.table.is_some() {
+            actions.extend([Action::SortNext, Action::SortReverse]);
+        }
+        if !self.groups.is_empty() {
+            actions.push(Action::Fold);
+        }
+        actions.push(Action::Confirm);
+        actions
+    }
+
+    fn hint(&self) -> String {
+        if self.searching {
+            return "(type to filter, Enter done, Esc clear)".to_string();
+        }
+
+        if self.anchor.is_some() {
+            let hint = self.keymap.hint(&[
+                (&[Action::Up, Action::Down], "extend"),
+                (&[Action::Toggle], "toggle range"),
+                (&[Action::Cancel], "exit range"),
+            ]);
+            return format!("-- RANGE -- {hint}");
+        }
+
+        let mut entries: Vec<(&[Action], &str)> = Vec::new();
+        if self.confirm.is_some() {
+            entries.push((&[Action::FocusConfirm, Action::FocusCancel], "select"));
+        } else {
+            entries.push((&[Action::Up, Action::Down], "navigate"));
+        }
+        if self.toggleable {
+            entries.push((&[Action::Toggle], "toggle"));
+            entries.push((&[Action::VisualRange], "range"));
+            entries.push((
+                &[
+                    Action::SelectAll,
+                    Action::SelectNone,
+                    Action::InvertSelection,
+                ],
+                "all/none/invert",
+            ));
+        }
+        entries.push((&[Action::Search], "search"));
+        if !self.groups.is_empty() {
+            entries.push((&[Action::Fold], "fold"));
+        }
+        if self.table.is_some() {
+            entries.push((&[Action::SortNext, Action::SortReverse], "sort/reverse"));
+        }
+        if self.preview.is_some() {
+            entries.push((&[Action::PreviewUp, Action::PreviewDown], "scroll preview"));
+        }
+        entries.push((&[Action::Confirm], "confirm"));
+        if self.confirm.is_none() {
+            entries.push((&[Action::Cancel], "cancel"));
+        }
+
+        self.keymap.hint(&entries)
+    }
+
+    /// Translate a mouse event into the action a key press would have
+    /// triggered, moving the cursor first for clicks on a row.
+    fn mouse_action(&mut self, mouse: MouseEvent) -> Option<Action> {
+        let position = Position::new(mouse.column, mouse.row);
+        let over_preview = self.areas.preview.is_some_and(|a| a.contains(position));
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp if over_preview => Some(Action::PreviewUp),
+            MouseEventKind::ScrollDown if over_preview => Some(Action::PreviewDown),
+            MouseEventKind::ScrollUp => Some(Action::Up),
+            MouseEventKind::ScrollDown => Some(Action::Down),
+
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(buttons) = &mut self.confirm {
+                    for (area, confirm) in [(self.areas.confirm, true), (self.areas.cancel, false)]
+                    {
+                        if area.is_some_and(|a| a.contains(position)) {
+                            buttons.selected_confirm = confirm;
+                            return Some(Action::Confirm);
                         }
                     }
-                    _ => {}
+                }
+
+                let list = self.areas.list;
+                if !list.contains(position) {
+                    return None;
+                }
+                let row = self.state.offset() + (position.y - list.y) as usize;
+                if row >= self.visible.len() {
+                    return None;
+                }
+                self.state.select(Some(row));
+
+                // The checkbox sits just after the highlight symbol, and in
+                // a group after the fold marker or the indent lining up with it
+                let on_header = matches!(self.visible[row], Row::Group(_));
+                let indent = if self.groups.is_empty() { 0 } else { 2 };
+                let checkbox = list.x + 3 + indent..list.x + 5 + indent;
+                if self.toggleable && checkbox.contains(&position.x) {
+                    Some(Action::Toggle)
+                } else if on_header {
+                    Some(Action::Fold)
+                } else {
+                    None
                 }
             }
+            _ => None,
         }
     }
+
+    /// Rows between the visual range's anchor and the cursor, inclusive.
+    fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.anchor?;
+        let start = self.visible.iter().position(|&r| r == Row::Item(anchor))?;
+        let end = self.state.selected()?;
+        Some(start.min(end)..=start.max(end))
+    }
+
+    /// Give every row in the visual range the opposite of the anchor's state,
+    /// then leave range mode.
+    fn toggle_range(&mut self) {
+        if let (Some(anchor), Some(range)) = (self.anchor, self.visual_range()) {
+            let value = !self.selected[anchor];
+            for row in range {
+                match self.visible[row] {
+                    Row::Item(i) => self.selected[i] = value,
+                    Row::Group(g) => {
+                        for &i in &self.groups[g].items {
+                            self.selected[i] = value;
+                        }
+                    }
+                }
+            }
+        }
+        self.anchor = None;
+    }
+
+    fn many(&self) -> SelectResult {
+        SelectResult::Many(
+            self.selected
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &sel)| sel.then_some(i))
+                .collect(),
+        )
+    }
+
+    /// A footer count such as `123/4000 selected`.
+    fn selected_count(&self) -> String {
+        let count = self.selected.iter().filter(|s| **s).count();
+        format!("{count}/{} selected", self.items.len())
+    }
+
+    fn move_cursor_up(&mut self) {
+        let cursor = self.state.selected().unwrap_or(0);
+        if cursor > 0 {
+            self.state.select(Some(cursor - 1));
+        }
+    }
+
+    fn move_cursor_down(&mut self) {
+        let cursor = self.state.selected().unwrap_or(0);
+        if cursor < self.visible.len().saturating_sub(1) {
+            self.state.select(Some(cursor + 1));
+        }
+    }
+}
+
+impl<T> App for SelectList<T> {
+    type Message = Feed<T>;
+    type Output = SelectResult;
+
+    fn update(&mut self, message: Message<Feed<T>>) -> crate::Result<Option<SelectResult>> {
+        Ok(match message {
+            Message::Input(event) => self.handle_event(event),
+            Message::External(feed) => {
+                self.apply_feed(feed);
+                None
+            }
+        })
+    }
+
+    fn view(&mut self, frame: &mut Frame) {
+        self.render(frame);
+    }
+}
+
+/// A group's header row: a fold marker, a tri-state checkbox when the list
+/// is toggleable, the title and how many items it holds.
+fn group_item(
+    group: &Group,
+    toggleable: bool,
+    selected: &[bool],
+    theme: &Theme,
+) -> ListItem<'static> {
+    let marker = if group.collapsed { "▸ " } else { "▾ " };
+    let mut spans = vec![Span::styled(marker, theme.accent)];
+    if toggleable {
+        let count = group.items.iter().filter(|&&i| selected[i]).count();
+        let (check, style) = match count {
+            0 => ("○ ", theme.unchecked),
+            n if n == group.items.len() => ("● ", theme.checked),
+            _ => ("◐ ", theme.checked),
+        };
+        spans.push(Span::styled(check, style));
+    }
+    spans.push(Span::styled(group.title.clone(), theme.header));
+    spans.push(Span::styled(
+        format!(" ({})", group.items.len()),
+        theme.muted,
+    ));
+    ListItem::new(Line::from(spans))
+}
+
+/// Split `line` into spans, patching the characters at `indices` with
+/// `matched` while keeping each span's own style everywhere else.
+pub(super) fn highlight(
+    line: Line<'static>,
+    indices: &[usize],
+    matched: Style,
+) -> Vec<Span<'static>> {
+    if indices.is_empty() {
+        return line.spans;
+    }
+
+    let mut spans = Vec::new();
+    let mut next = indices.iter().peekable();
+    let mut i = 0;
+
+    for span in line.spans {
+        let base = line.style.patch(span.style);
+        let mut current = String::new();
+        let mut current_matched = false;
+
+        for c in span.content.chars() {
+            let is_match = next.next_if_eq(&&i).is_some();
+            if is_match != current_matched && !current.is_empty() {
+                let style = if current_matched {
+                    base.patch(matched)
+                } else {
+                    base
+                };
+                spans.push(Span::styled(std::mem::take(&mut current), style));
+            }
+            current_matched = is_match;
+            current.push(c);
+            i += 1;
+        }
+
+        if !current.is_empty() {
+            let style = if current_matched {
+                base.patch(matched)
+            } else {
+                base
+            };
+            spans.push(Span::styled(current, style));
+        }
+    }
+
+    spans
+}
+
+/// Parse 1-based numbers and inclusive ranges such as `1, 3-5` into 0-based
+/// indices below `len`.
+fn parse_selection(input: &str, len: usize) -> Option<Vec<usize>> {
+    let mut indices = Vec::new();
+
+    for token in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        if token.is_empty() {
+            continue;
+        }
+
+        let (start, end) = match token.split_once('-') {
+            Some((start, end)) => (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?),
+            None => {
+                let n = token.parse::<usize>().ok()?;
+                (n, n)
+            }
+        };
+
+        if start == 0 || start > end || end > len {
+            return None;
+        }
+
+        indices.extend(start - 1..end);
+    }
+
+    (!indices.is_empty()).then_some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::api::terminal::ScriptedEvents;
+
+    fn fruits() -> SelectList<&'static str> {
+        SelectList::new(vec!["apple", "banana", "cherry"], "Fruits", |s: &&str| {
+            s.to_string()
+        })
+    }
+
+    #[test]
+    fn enter_confirms_plain_list() {
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::keys([KeyCode::Enter]));
+        let result = fruits().run(&mut terminal).unwrap();
+        assert_eq!(result, SelectResult::Picked(0));
+    }
+
+    #[test]
+    fn esc_cancels() {
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::keys([KeyCode::Esc]));
+        let result = fruits().run(&mut terminal).unwrap();
+        assert!(matches!(result, SelectResult::Cancelled));
+    }
+
+    #[test]
+    fn renders_header_items_and_hint() {
+        let mut terminal = InlineTerminal::headless(70, 8, ScriptedEvents::keys([KeyCode::Enter]));
+        fruits().run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[0], "Fruits");
+        assert_eq!(rendered[2], " > apple");
+        assert_eq!(rendered[3], "   banana");
+        assert_eq!(
+            rendered[7],
+            "  (↑/↓ navigate, / search, Enter confirm, Esc cancel)"
+        );
+    }
+
+    #[test]
+    fn reflows_on_resize() {
+        let events = [
+            Event::Resize(30, 6),
+            Event::Key(KeyEvent::from(KeyCode::Enter)),
+        ];
+        let mut terminal = InlineTerminal::headless(70, 8, ScriptedEvents::new(events));
+        fruits().run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[5], "  (↑/↓ navigate, / search, Ent");
+        assert_eq!(rendered[6], "");
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn mouse_toggles_rows_and_clicks_buttons() {
+        let click = MouseEventKind::Down(MouseButton::Left);
+        let events = [
+            // Checkbox on the banana row, then the cherry row's text
+            mouse(click, 3, 3),
+            mouse(click, 10, 4),
+            mouse(MouseEventKind::ScrollUp, 10, 4),
+            // The Save button
+            mouse(click, 4, 7),
+        ];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_confirm("Save", "Cancel").with_toggleable();
+
+        let result = select.run(&mut terminal).unwrap();
+
+        assert_eq!(result, SelectResult::Many(vec![0, 2]));
+        assert_eq!(select.unselected_items(), vec![&"banana"]);
+        assert_eq!(select.cursor_item(), Some(1));
+    }
+
+    #[test]
+    fn clicking_cancel_button_cancels() {
+        let click = MouseEventKind::Down(MouseButton::Left);
+        let events = [mouse(click, 12, 7)];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_confirm("Save", "Cancel");
+
+        let result = select.run(&mut terminal).unwrap();
+        assert!(matches!(result, SelectResult::Cancelled));
+    }
+
+    #[test]
+    fn toggles_and_confirms_with_buttons() {
+        let keys = [
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Tab,
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_confirm("Save", "Cancel").with_toggleable();
+
+        let result = select.run(&mut terminal).unwrap();
+
+        assert_eq!(result, SelectResult::Many(vec![0, 2]));
+        assert_eq!(select.selected_items(), vec![&"apple", &"cherry"]);
+        assert_eq!(select.unselected_items(), vec![&"banana"]);
+    }
+
+    #[test]
+    fn y_does_not_confirm_the_list() {
+        let keys = [KeyCode::Char('y'), KeyCode::Esc];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_confirm("Save", "Cancel");
+
+        let result = select.run(&mut terminal).unwrap();
+        assert!(matches!(result, SelectResult::Cancelled));
+    }
+
+    #[test]
+    fn enter_on_cancel_button_cancels() {
+        let keys = [KeyCode::Char(' '), KeyCode::Enter];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_confirm("Save", "Cancel").with_toggleable();
+
+        let result = select.run(&mut terminal).unwrap();
+        assert!(matches!(result, SelectResult::Cancelled));
+    }
+
+    #[test]
+    fn ctrl_c_cancels_mid_search() {
+        let events = [
+            Event::Key(KeyCode::Char('/').into()),
+            Event::Key(KeyCode::Char('a').into()),
+            Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+        ];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_toggleable();
+
+        let result = select.run(&mut terminal).unwrap();
+
+        assert_eq!(result, SelectResult::Cancelled);
+        // The interrupt isn't typed into the query
+        assert!(terminal.rendered()[1].starts_with("/a█"));
+    }
+
+    #[test]
+    fn search_narrows_and_toggles_underlying_item() {
+        let keys = [
+            KeyCode::Char('/'),
+            KeyCode::Char('c'),
+            KeyCode::Char('h'),
+            KeyCode::Enter,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert!(rendered[1].starts_with("/ch"));
+        assert!(rendered[1].contains("1/3 matches"));
+        assert_eq!(rendered[2], " > ○ cherry");
+        assert_eq!(select.unselected_items(), vec![&"cherry"]);
+    }
+
+    #[test]
+    fn clearing_search_restores_list_and_cursor() {
+        let keys = [
+            KeyCode::Down,
+            KeyCode::Char('/'),
+            KeyCode::Char('a'),
+            KeyCode::Char('p'),
+            KeyCode::Esc,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(80, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[1], "");
+        assert_eq!(rendered[4], "   ● cherry");
+        assert_eq!(select.unselected_items(), vec![&"banana"]);
+    }
+
+    #[test]
+    fn column_is_right_aligned() {
+        let mut terminal = InlineTerminal::headless(40, 8, ScriptedEvents::keys([KeyCode::Enter]));
+        let mut select = fruits().with_column(|s: &&str| format!("{}", s.len()));
+
+        select.run(&mut terminal).unwrap();
+
+        assert_eq!(
+            terminal.rendered()[2],
+            format!(" > apple{}5", " ".repeat(31))
+        );
+    }
+
+    #[test]
+    fn preview_follows_cursor() {
+        let keys = [KeyCode::Down, KeyCode::Enter];
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::keys(keys));
+        let mut select =
+            fruits().with_preview(PreviewPosition::Right, |s: &&str| format!("{s} details"));
+
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert!(rendered[2].ends_with("│ banana details"));
+        assert!(rendered[3].starts_with(" > banana"));
+    }
+
+    #[test]
+    fn preview_scrolls_separately_and_resets_on_move() {
+        let key = |code| Event::Key(KeyEvent::from(code));
+        let shift_down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT));
+        let preview = |_: &&str| "one\ntwo\nthree";
+
+        let events = [shift_down.clone(), shift_down.clone(), key(KeyCode::Enter)];
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_preview(PreviewPosition::Bottom, preview);
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[2], " > apple");
+        assert!(rendered.iter().any(|row| row == "three"));
+        assert!(!rendered.iter().any(|row| row == "one"));
+
+        let events = [
+            shift_down,
+            key(KeyCode::Down),
+            key(KeyCode::Up),
+            key(KeyCode::Enter),
+        ];
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_preview(PreviewPosition::Bottom, preview);
+        select.run(&mut terminal).unwrap();
+
+        assert!(terminal.rendered().iter().any(|row| row == "one"));
+    }
+
+    #[test]
+    fn preview_scrolls_through_wrapped_lines() {
+        let shift_down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::SHIFT));
+        let events = [
+            shift_down.clone(),
+            shift_down,
+            Event::Key(KeyEvent::from(KeyCode::Enter)),
+        ];
+        let mut terminal = InlineTerminal::headless(30, 8, ScriptedEvents::new(events));
+        let mut select = fruits().with_preview(
+            PreviewPosition::Right,
+            |_: &&str| "alpha beta gamma delta epsilon",
+        );
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert!(!rendered.iter().any(|row| row.contains("alpha")));
+        assert!(rendered.iter().any(|row| row.contains("epsilon")));
+    }
+
+    #[test]
+    fn bulk_selection_and_footer_count() {
+        let keys = [KeyCode::Char('D'), KeyCode::Down, KeyCode::Char(' ')];
+        let keys = keys.into_iter().chain([KeyCode::Char('I'), KeyCode::Enter]);
+        let mut terminal = InlineTerminal::headless(100, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        assert_eq!(select.selected_items(), vec![&"apple", &"cherry"]);
+        assert!(terminal.rendered()[7].starts_with("  2/3 selected  ("));
+    }
+
+    #[test]
+    fn visual_range_toggles_span() {
+        let keys = [
+            KeyCode::Down,
+            KeyCode::Char('V'),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(100, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        assert_eq!(select.selected_items(), vec![&"apple"]);
+    }
+
+    #[test]
+    fn toggle_filtered_only_touches_matches() {
+        let keys = [
+            KeyCode::Char('/'),
+            KeyCode::Char('a'),
+            KeyCode::Char('n'),
+            KeyCode::Enter,
+            KeyCode::Char('T'),
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(100, 8, ScriptedEvents::keys(keys));
+        let mut select = fruits().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        assert_eq!(select.selected_items(), vec![&"apple", &"cherry"]);
+    }
+
+    fn grouped() -> SelectList<&'static str> {
+        SelectList::new(
+            vec!["git status", "cargo build", "git push"],
+            "Commands",
+            |s: &&str| s.to_string(),
+        )
+        .with_groups(|s: &&str| s.split(' ').next().unwrap_or_default().to_string())
+    }
+
+    #[test]
+    fn group_toggles_cascade_with_tri_state() {
+        let keys = [
+            // Clear the git group from its header, then pick one back
+            KeyCode::Char(' '),
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(60, 10, ScriptedEvents::keys(keys));
+        let mut select = grouped().with_toggleable();
+
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[2].trim_end(), "   ▾ ◐ git (2)");
+        assert_eq!(rendered[3].trim_end(), " >   ● git status");
+        assert_eq!(rendered[4].trim_end(), "     ○ git push");
+        assert_eq!(rendered[5].trim_end(), "   ▾ ● cargo (1)");
+        assert_eq!(select.selected_items(), vec![&"git status", &"cargo build"]);
+    }
+
+    #[test]
+    fn enter_folds_headers_of_plain_list() {
+        let keys = [KeyCode::Enter, KeyCode::Down, KeyCode::Down, KeyCode::Enter];
+        let mut terminal = InlineTerminal::headless(60, 10, ScriptedEvents::keys(keys));
+        let mut select = grouped();
+
+        let result = select.run(&mut terminal).unwrap();
+
+        // Folding git leaves cargo build on the third row
+        assert_eq!(result, SelectResult::Picked(1));
+        assert_eq!(terminal.rendered()[2].trim_end(), "   ▸ git (2)");
+    }
+
+    #[test]
+    fn feed_adds_items_while_on_screen() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        tx.send(Feed::Items(vec!["banana", "cherry"])).unwrap();
+        tx.send(Feed::Status(Some("Still looking…".into())))
+            .unwrap();
+
+        let keys = [KeyCode::Down, KeyCode::Down, KeyCode::Enter];
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::keys(keys));
+        let mut select =
+            SelectList::new(vec!["apple"], "Fruits", |s: &&str| s.to_string()).with_feed(rx);
+
+        let result = select.run(&mut terminal).unwrap();
+
+        assert_eq!(result, SelectResult::Picked(2));
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[1], "Still looking…");
+        assert_eq!(rendered[4], " > cherry");
+    }
+
+    #[test]
+    fn plain_prompt_toggles_ranges() {
+        let mut select = fruits().with_toggleable();
+        let mut output = Vec::new();
+
+        let result = select
+            .run_plain(&mut "1-2\n2\n\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(result, SelectResult::Many(vec![1, 2]));
+        assert_eq!(select.unselected_items(), vec![&"apple"]);
+    }
+
+    #[test]
+    fn plain_prompt_bulk_words() {
+        let mut select = fruits().with_toggleable();
+        let mut output = Vec::new();
+
+        select
+            .run_plain(&mut "none\n2\ninvert\n\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(select.selected_items(), vec![&"apple", &"cherry"]);
+        assert!(String::from_utf8(output).unwrap().contains("1/3 selected"));
+    }
+
+    #[test]
+    fn plain_prompt_picks_one() {
+        let mut select = fruits();
+        let mut output = Vec::new();
+
+        let result = select
+            .run_plain(&mut "1 3\n3\n".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(result, SelectResult::Picked(2));
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Pick exactly one item")
+        );
+    }
+
+    #[test]
+    fn enter_without_matches_picks_nothing() {
+        let keys = [
+            KeyCode::Char('/'),
+            KeyCode::Char('x'),
+            KeyCode::Enter,
+            KeyCode::Enter,
+            KeyCode::Esc,
+            KeyCode::Enter,
+        ];
+        let mut terminal = InlineTerminal::headless(60, 8, ScriptedEvents::keys(keys));
+        let result = fruits().run(&mut terminal).unwrap();
+        // Esc clears the search and the cursor goes back to the first row
+        assert_eq!(result, SelectResult::Picked(0));
+    }
+
+    #[test]
+    fn plain_prompt_lists_once_and_on_request() {
+        let mut select = fruits().with_toggleable();
+        let mut output = Vec::new();
+
+        select
+            .run_plain(&mut "2\nall\nl\n\n".as_bytes(), &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("Fruits").count(), 2);
+        assert_eq!(output.matches("1. [x] apple").count(), 2);
+        // Toggling shows just the changed row and the count
+        assert!(output.contains("   2. [ ] banana\n2/3 selected\n"));
+        assert!(output.contains("3/3 selected\n"));
+    }
+
+    #[test]
+    fn plain_prompt_lists_groups_and_tables() {
+        let mut select = fruits().with_groups(|s: &&str| s.len().to_string());
+        let mut output = Vec::new();
+        select
+            .run_plain(&mut "2\n".as_bytes(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("      5 (1)\n   1. apple\n"));
+
+        let columns = vec![
+            Column::new("Fruit", |s: &&str| s.to_string()),
+            Column::new("Length", |s: &&str| s.len().to_string()),
+        ];
+        let mut select = SelectList::table(vec!["apple", "fig"], "Fruits", columns);
+        let mut output = Vec::new();
+        let result = select
+            .run_plain(&mut "2\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(result, SelectResult::Picked(1));
+        let output = String::from_utf8(output).unwrap();
+        assert!(
+            output
+                .starts_with("Fruits\n      Fruit    Length\n   1. apple    5\n   2. fig      3\n")
+        );
+    }
+
+    #[test]
+    fn plain_prompt_without_input_errors() {
+        let mut select = fruits();
+        let result = select.run_plain(&mut "".as_bytes(), &mut Vec::new());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn table_sorts_by_column() {
+        let alt = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT));
+        let events = [
+            alt('s'),
+            alt('s'),
+            alt('r'),
+            Event::Key(KeyEvent::from(KeyCode::Enter)),
+        ];
+        let mut terminal = InlineTerminal::headless(40, 8, ScriptedEvents::new(events));
+        let columns = vec![
+            Column::new("Fruit", |s: &&str| s.to_string()),
+            Column::new("Length", |s: &&str| s.len().to_string())
+                .with_sort_key(|s: &&str| s.len())
+                .right_aligned(),
+        ];
+        let mut select = SelectList::table(vec!["fig", "banana", "apple"], "Fruits", columns);
+
+        let result = select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[2], "   Fruit    Length ▼");
+        assert_eq!(rendered[3], "   banana          6");
+        assert_eq!(rendered[4], "   apple           5");
+        assert_eq!(rendered[5], " > fig             3");
+        // Sorting only changes the view, the cursor stays on its item
+        assert_eq!(result, SelectResult::Picked(0));
+    }
+
+    #[test]
+    fn formats_only_rows_in_view() {
+        let formatted = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = formatted.clone();
+        let mut select = SelectList::new((0..100_000).collect(), "Numbers", move |n: &usize| {
+            counter.set(counter.get() + 1);
+            n.to_string()
+        });
+        let keys = [KeyCode::End, KeyCode::Up, KeyCode::Enter];
+        let mut terminal = InlineTerminal::headless(40, 8, ScriptedEvents::keys(keys));
+
+        select.run(&mut terminal).unwrap();
+
+        let rendered = terminal.rendered();
+        assert_eq!(rendered[2], "   99995");
+        assert_eq!(rendered[5], " > 99998");
+        // The first and last five rows, each formatted once
+        assert_eq!(formatted.get(), 10);
+    }
+
+    #[test]
+    fn parses_selection() {
+        assert_eq!(parse_selection("1, 3-4", 5), Some(vec![0, 2, 3]));
+        assert_eq!(parse_selection("0", 5), None);
+        assert_eq!(parse_selection("4-2", 5), None);
+        assert_eq!(parse_selection("6", 5), None);
+        assert_eq!(parse_selection("", 5), None);
+    }
 }
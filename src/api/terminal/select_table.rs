@@ -0,0 +1,335 @@
+use std::cmp::Ordering;
+
+use ratatui::prelude::*;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{SelectList, Theme};
+
+type CellFn<T> = Box<dyn Fn(&T) -> String>;
+type CompareFn<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
+/// Space between two columns
+const GAP: usize = 2;
+/// Columns never shrink below this, so there's always room for `x…`
+const MIN_WIDTH: usize = 3;
+
+/// A [`SelectList`] laid out as a table, made with [`SelectList::table`].
+pub(crate) type SelectTable<T> = SelectList<T>;
+
+/// One column of a [`SelectTable`].
+pub(crate) struct Column<T> {
+    title: String,
+    cell_fn: CellFn<T>,
+    /// How to sort by this column, comparing the cell text when unset
+    compare_fn: Option<CompareFn<T>>,
+    right_aligned: bool,
+    style: Style,
+}
+
+impl<T> Column<T> {
+    pub(crate) fn new(title: impl Into<String>, cell_fn: impl Fn(&T) -> String + 'static) -> Self {
+        Self {
+            title: title.into(),
+            cell_fn: Box::new(cell_fn),
+            compare_fn: None,
+            right_aligned: false,
+            style: Style::default(),
+        }
+    }
+
+    /// Sort by `key` instead of the cell text, e.g. to sort counts
+    /// numerically.
+    pub(crate) fn with_sort_key<K: Ord>(mut self, key: impl Fn(&T) -> K + 'static) -> Self {
+        self.compare_fn = Some(Box::new(move |a, b| key(a).cmp(&key(b))));
+        self
+    }
+
+    /// Line cells up on the right, for numbers.
+    pub(crate) fn right_aligned(mut self) -> Self {
+        self.right_aligned = true;
+        self
+    }
+
+    pub(crate) fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// The columns of a table and which one it's sorted by.
+pub(super) struct Table<T> {
+    columns: Vec<Column<T>>,
+    /// The column sorted by and whether it's descending, or `None` to keep
+    /// the items in the order they were given
+    sort: Option<(usize, bool)>,
+    /// The width of each column's widest cell, measured once up front
+    natural: Vec<usize>,
+}
+
+impl<T> Table<T> {
+    pub(super) fn new(columns: Vec<Column<T>>, items: &[T]) -> Self {
+        let natural = columns
+            .iter()
+            // Leave room for the sort arrow
+            .map(|c| c.title.width() + 2)
+            .collect();
+        let mut table = Self {
+            columns,
+            sort: None,
+            natural,
+        };
+        table.measure(items);
+        table
+    }
+
+    /// Widen the columns to fit `items`, for items added after the table
+    /// was made.
+    pub(super) fn measure(&mut self, items: &[T]) {
+        for item in items {
+            for (width, column) in self.natural.iter_mut().zip(&self.columns) {
+                *width = (*width).max((column.cell_fn)(item).width());
+            }
+        }
+    }
+
+    /// Sort by the next column, going back to the original order after the
+    /// last one.
+    pub(super) fn sort_next(&mut self) {
+        self.sort = match self.sort {
+            None if !self.columns.is_empty() => Some((0, false)),
+            Some((column, _)) if column + 1 < self.columns.len() => Some((column + 1, false)),
+            _ => None,
+        };
+    }
+
+    pub(super) fn reverse_sort(&mut self) {
+        if let Some((_, descending)) = &mut self.sort {
+            *descending = !*descending;
+        }
+    }
+
+    /// Reorder `rows` (indices into `items`) by the current sort, carrying
+    /// each row's extra data along with it.
+    pub(super) fn sort_rows<D>(&self, items: &[T], rows: &mut Vec<(usize, D)>) {
+        let Some((column, descending)) = self.sort else {
+            return;
+        };
+        let column = &self.columns[column];
+        let direct = |order: Ordering| if descending { order.reverse() } else { order };
+
+        match &column.compare_fn {
+            Some(compare_fn) => {
+                rows.sort_by(|(a, _), (b, _)| direct(compare_fn(&items[*a], &items[*b])));
+            }
+            None => {
+                // Format each cell once up front rather than twice for
+                // every comparison
+                let mut keyed: Vec<_> = rows
+                    .drain(..)
+                    .map(|row| ((column.cell_fn)(&items[row.0]), row))
+                    .collect();
+                keyed.sort_by(|(a, _), (b, _)| direct(a.cmp(b)));
+                rows.extend(keyed.into_iter().map(|(_, row)| row));
+            }
+        }
+    }
+
+    /// The cells of `item` joined by spaces, which is what search matches
+    /// against.
+    pub(super) fn text(&self, item: &T) -> String {
+        self.cells(item).join(" ")
+    }
+
+    fn cells(&self, item: &T) -> Vec<String> {
+        self.columns.iter().map(|c| (c.cell_fn)(item)).collect()
+    }
+
+    /// Fit each column to its widest cell, then shrink the widest columns
+    /// until the table fits in `available`.
+    pub(super) fn widths(&self, available: usize) -> Vec<usize> {
+        let mut widths = self.natural.clone();
+
+        let gaps = GAP * self.columns.len().saturating_sub(1);
+        let mut total = widths.iter().sum::<usize>() + gaps;
+        while total > available {
+            let Some(widest) = widths
+                .iter_mut()
+                .filter(|w| **w > MIN_WIDTH)
+                .max_by_key(|w| **w)
+            else {
+                break;
+            };
+            *widest -= 1;
+            total -= 1;
+        }
+
+        widths
+    }
+
+    /// The column titles, marking the sorted column with an arrow.
+    pub(super) fn header(&self, widths: &[usize], theme: &Theme) -> Line<'static> {
+        let mut spans = Vec::new();
+        for (i, (column, &width)) in self.columns.iter().zip(widths).enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" ".repeat(GAP)));
+            }
+            let (title, style) = match self.sort {
+                Some((sorted, descending)) if sorted == i => {
+                    let arrow = if descending { "▼" } else { "▲" };
+                    (format!("{} {arrow}", column.title), theme.accent)
+                }
+                _ => (column.title.clone(), theme.muted),
+            };
+            spans.push(Span::styled(
+                pad(&truncate(&title, width), width, column.right_aligned),
+                style,
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// One row of cells, with `indices` (positions in [`Self::text`])
+    /// highlighted in `matched`.
+    pub(super) fn row(
+        &self,
+        item: &T,
+        widths: &[usize],
+        indices: &[usize],
+        matched: Style,
+    ) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        let mut offset = 0;
+        for (i, ((column, cell), &width)) in self
+            .columns
+            .iter()
+            .zip(self.cells(item))
+            .zip(widths)
+            .enumerate()
+        {
+            if i > 0 {
+                spans.push(Span::raw(" ".repeat(GAP)));
+            }
+
+            let len = cell.chars().count();
+            let local: Vec<usize> = indices
+                .iter()
+                .filter(|&&n| n >= offset && n < offset + len)
+                .map(|n| n - offset)
+                .collect();
+            // Account for the space `text` joins cells with
+            offset += len + 1;
+
+            let cell = truncate(&cell, width);
+            let fill = " ".repeat(width.saturating_sub(cell.width()));
+            if column.right_aligned {
+                spans.push(Span::raw(fill.clone()));
+            }
+            let line = Line::from(Span::styled(cell, column.style));
+            spans.extend(super::select_list::highlight(line, &local, matched));
+            if !column.right_aligned && i + 1 < self.columns.len() {
+                spans.push(Span::raw(fill));
+            }
+        }
+        spans
+    }
+
+    /// The header and every item as aligned plain text, for the plain
+    /// prompt.
+    pub(super) fn plain_rows(&self, items: &[T]) -> (String, Vec<String>) {
+        let text = |spans: Vec<Span<'static>>| {
+            let line: String = spans.into_iter().map(|s| s.content).collect();
+            line.trim_end().to_string()
+        };
+
+        let widths = self.widths(usize::MAX);
+        let header = text(self.header(&widths, &Theme::plain()).spans);
+        let rows = items
+            .iter()
+            .map(|item| text(self.row(item, &widths, &[], Style::default())))
+            .collect();
+        (header, rows)
+    }
+}
+
+/// Cut `text` down to `width` columns, ending in `…` when anything was cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+fn pad(text: &str, width: usize, right_aligned: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(text.width()));
+    if right_aligned {
+        format!("{fill}{text}")
+    } else {
+        format!("{text}{fill}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(items: &[(&'static str, u32)]) -> Table<(&'static str, u32)> {
+        Table::new(
+            vec![
+                Column::new("Name", |(name, _): &(&str, u32)| name.to_string()),
+                Column::new("Uses", |(_, uses): &(&str, u32)| uses.to_string())
+                    .with_sort_key(|(_, uses): &(&str, u32)| *uses)
+                    .right_aligned(),
+            ],
+            items,
+        )
+    }
+
+    #[test]
+    fn truncates_with_ellipsis() {
+        assert_eq!(truncate("checkout", 5), "chec…");
+        assert_eq!(truncate("ls", 5), "ls");
+        assert_eq!(truncate("日本語", 4), "日…");
+    }
+
+    #[test]
+    fn fits_columns_to_the_space() {
+        let items = [("git status", 12), ("cargo build --release", 3)];
+        let table = table(&items);
+        assert_eq!(table.widths(80), vec![21, 6]);
+        // The widest column gives way first
+        assert_eq!(table.widths(20), vec![12, 6]);
+    }
+
+    #[test]
+    fn sorts_by_key_and_reverses() {
+        let items = [("b", 2), ("a", 10), ("c", 1)];
+        let mut table = table(&items);
+        let order = |table: &Table<_>| {
+            let mut rows: Vec<(usize, ())> = (0..items.len()).map(|i| (i, ())).collect();
+            table.sort_rows(&items, &mut rows);
+            rows.into_iter().map(|(i, _)| i).collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(&table), vec![0, 1, 2]);
+        table.sort_next();
+        assert_eq!(order(&table), vec![1, 0, 2]);
+        table.sort_next();
+        assert_eq!(order(&table), vec![2, 0, 1]);
+        table.reverse_sort();
+        assert_eq!(order(&table), vec![1, 0, 2]);
+        table.sort_next();
+        assert_eq!(order(&table), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,497 @@
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{prelude::*, widgets::Paragraph};
+
+use super::{InlineTerminal, TerminalBackend, TerminalErrors, Theme, is_interactive, is_interrupt};
+
+type Validator = Box<dyn Fn(&str) -> Result<(), String>>;
+
+/// What a key press did to a [`TextInput`].
+pub(super) enum InputOutcome {
+    Editing,
+    Submitted,
+    Cancelled,
+}
+
+/// A single line of editable text with readline-style keys, history and
+/// validation.
+pub(crate) struct TextInput {
+    label: String,
+    value: String,
+    /// Cursor position, in characters
+    cursor: usize,
+    placeholder: String,
+    /// Earlier entries, oldest first, browsed with ↑/↓
+    history: Vec<String>,
+    /// The history entry being shown, and what was typed before browsing
+    browsing: Option<(usize, String)>,
+    validate: Option<Validator>,
+    error: Option<String>,
+    theme: Theme,
+}
+
+impl TextInput {
+    pub(crate) fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: String::new(),
+            cursor: 0,
+            placeholder: String::new(),
+            history: Vec::new(),
+            browsing: None,
+            validate: None,
+            error: None,
+            theme: Theme::current(),
+        }
+    }
+
+    /// Start with `value` already typed and the cursor at its end.
+    pub(crate) fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = value.into();
+        self.cursor = self.value.chars().count();
+        self
+    }
+
+    /// Dimmed text shown while the input is empty.
+    pub(crate) fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Entries to step through with ↑/↓, oldest first.
+    pub(crate) fn with_history(mut self, history: Vec<String>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Check the value before it's submitted. The error message is shown
+    /// under the input and submitting is refused until it's fixed.
+    pub(crate) fn with_validation(
+        mut self,
+        validate: impl Fn(&str) -> Result<(), String> + 'static,
+    ) -> Self {
+        self.validate = Some(Box::new(validate));
+        self
+    }
+
+    pub(crate) fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub(super) fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub(super) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Run the input inline, or as a plain question on stdin/stderr when
+    /// stdout isn't a terminal. Returns `None` if it was cancelled.
+    pub(crate) fn prompt(&mut self, width: u16) -> crate::Result<Option<String>> {
+        if !is_interactive() {
+            return self.run_plain(&mut std::io::stdin().lock(), &mut std::io::stderr());
+        }
+
+        let mut terminal = InlineTerminal::new(2, width)?;
+        let result = self.run(&mut terminal)?;
+        terminal.cleanup()?;
+        Ok(result)
+    }
+
+    /// Run the non-interactive fallback. An empty line keeps the current
+    /// value.
+    pub(crate) fn run_plain(
+        &mut self,
+        input: &mut impl std::io::BufRead,
+        output: &mut impl std::io::Write,
+    ) -> crate::Result<Option<String>> {
+        let mut read_any = false;
+
+        loop {
+            if self.value.is_empty() {
+                write!(output, "{}: ", self.label)?;
+            } else {
+                write!(output, "{} [{}]: ", self.label, self.value)?;
+            }
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(output)?;
+                if !read_any {
+                    return Err(TerminalErrors::NoInput.into());
+                }
+                return Ok(None);
+            }
+            read_any = true;
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            if !line.is_empty() {
+                self.set_value(line.to_string());
+            }
+
+            if self.check() {
+                return Ok(Some(self.value.clone()));
+            }
+            if let Some(error) = &self.error {
+                writeln!(output, "{error}")?;
+            }
+        }
+    }
+
+    pub(crate) fn run<B: TerminalBackend>(
+        &mut self,
+        terminal: &mut InlineTerminal<B>,
+    ) -> crate::Result<Option<String>> {
+        loop {
+            let mut input = vec![Span::styled(format!("{}: ", self.label), self.theme.header)];
+            input.extend(self.spans(true));
+            let status = match &self.error {
+                Some(error) => Span::styled(format!("  {error}"), self.theme.error),
+                None => Span::styled(format!("  {}", Self::hint()), self.theme.muted),
+            };
+
+            terminal.draw(|f| {
+                let rows = Layout::vertical([Constraint::Length(1), Constraint::Length(1)])
+                    .split(f.area());
+                f.render_widget(Paragraph::new(Line::from(input)), rows[0]);
+                f.render_widget(Paragraph::new(status), rows[1]);
+            })?;
+
+            if let Some(Event::Key(key)) = terminal.next_event(Duration::from_millis(100))? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                match self.handle_key(key) {
+                    InputOutcome::Editing => {}
+                    InputOutcome::Submitted => return Ok(Some(self.value.clone())),
+                    InputOutcome::Cancelled => return Ok(None),
+                }
+            }
+        }
+    }
+
+    pub(super) fn hint() -> &'static str {
+        "(Enter submit, Esc cancel, ↑/↓ history)"
+    }
+
+    /// Run the validator, remembering its message. Returns whether the value
+    /// is acceptable.
+    pub(super) fn check(&mut self) -> bool {
+        self.error = self
+            .validate
+            .as_ref()
+            .and_then(|validate| validate(&self.value).err());
+        self.error.is_none()
+    }
+
+    /// The value with a block cursor when focused, or the placeholder.
+    pub(super) fn spans(&self, focused: bool) -> Vec<Span<'static>> {
+        let cursor_style = Style::new().reversed();
+
+        if self.value.is_empty() {
+            let mut placeholder = self.placeholder.chars();
+            return match (focused, placeholder.next()) {
+                (true, Some(first)) => vec![
+                    Span::styled(first.to_string(), cursor_style.patch(self.theme.muted)),
+                    Span::styled(placeholder.collect::<String>(), self.theme.muted),
+                ],
+                (true, None) => vec![Span::styled(" ", cursor_style)],
+                (false, _) => vec![Span::styled(self.placeholder.clone(), self.theme.muted)],
+            };
+        }
+
+        if !focused {
+            return vec![Span::raw(self.value.clone())];
+        }
+
+        let before: String = self.value.chars().take(self.cursor).collect();
+        let mut rest = self.value.chars().skip(self.cursor);
+        let at = rest.next().map(String::from).unwrap_or_else(|| " ".into());
+        vec![
+            Span::raw(before),
+            Span::styled(at, cursor_style),
+            Span::raw(rest.collect::<String>()),
+        ]
+    }
+
+    /// Apply a key press. Enter only submits once the value validates.
+    pub(super) fn handle_key(&mut self, key: KeyEvent) -> InputOutcome {
+        if is_interrupt(&key) {
+            return InputOutcome::Cancelled;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let len = self.value.chars().count();
+
+        // Word jumps: M-b/M-f, or C-←/C-→ and M-←/M-→
+        let word_jump = match key.code {
+            KeyCode::Char('b') if alt => Some(false),
+            KeyCode::Char('f') if alt => Some(true),
+            KeyCode::Left if ctrl || alt => Some(false),
+            KeyCode::Right if ctrl || alt => Some(true),
+            _ => None,
+        };
+        if let Some(forward) = word_jump {
+            self.cursor = if forward {
+                self.word_end()
+            } else {
+                self.word_start()
+            };
+            return InputOutcome::Editing;
+        }
+
+        // Readline control keys do the same as their dedicated keys
+        let code = match key.code {
+            KeyCode::Char('a') if ctrl => KeyCode::Home,
+            KeyCode::Char('e') if ctrl => KeyCode::End,
+            KeyCode::Char('b') if ctrl => KeyCode::Left,
+            KeyCode::Char('f') if ctrl => KeyCode::Right,
+            KeyCode::Char('h') if ctrl => KeyCode::Backspace,
+            KeyCode::Char('d') if ctrl => KeyCode::Delete,
+            code => code,
+        };
+
+        match code {
+            KeyCode::Enter if self.check() => return InputOutcome::Submitted,
+            KeyCode::Esc => return InputOutcome::Cancelled,
+
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(len),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = len,
+
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.remove(self.cursor..self.cursor + 1);
+            }
+            KeyCode::Delete if self.cursor < len => self.remove(self.cursor..self.cursor + 1),
+            KeyCode::Char('w') if ctrl => {
+                let start = self.word_start();
+                self.remove(start..self.cursor);
+                self.cursor = start;
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.remove(0..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if ctrl => self.remove(self.cursor..len),
+
+            KeyCode::Up => self.browse_history(true),
+            KeyCode::Down => self.browse_history(false),
+
+            KeyCode::Char(c) if !ctrl && !alt => {
+                let at = self.byte_index(self.cursor);
+                self.value.insert(at, c);
+                self.cursor += 1;
+                self.error = None;
+            }
+            _ => {}
+        }
+
+        InputOutcome::Editing
+    }
+
+    fn set_value(&mut self, value: String) {
+        self.cursor = value.chars().count();
+        self.value = value;
+        self.error = None;
+    }
+
+    /// Step to an older (`back`) or newer history entry, returning to what
+    /// was typed once past the newest.
+    fn browse_history(&mut self, back: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let next = match (&self.browsing, back) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => return,
+            (Some((i, _)), true) => Some(i.saturating_sub(1)),
+            (Some((i, _)), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+
+        match next {
+            Some(i) => {
+                let draft = match self.browsing.take() {
+                    Some((_, draft)) => draft,
+                    None => self.value.clone(),
+                };
+                self.set_value(self.history[i].clone());
+                self.browsing = Some((i, draft));
+            }
+            None => {
+                if let Some((_, draft)) = self.browsing.take() {
+                    self.set_value(draft);
+                }
+            }
+        }
+    }
+
+    fn byte_index(&self, chars: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(chars)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    /// Remove the characters in `range`.
+    fn remove(&mut self, range: std::ops::Range<usize>) {
+        let start = self.byte_index(range.start);
+        let end = self.byte_index(range.end);
+        self.value.replace_range(start..end, "");
+        self.error = None;
+    }
+
+    /// Where the word before the cursor starts.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Where the word after the cursor ends.
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::terminal::ScriptedEvents;
+
+    fn run(input: TextInput, events: Vec<Event>) -> (Option<String>, Vec<String>) {
+        let mut input = input;
+        let mut terminal = InlineTerminal::headless(50, 2, ScriptedEvents::new(events));
+        let result = input.run(&mut terminal).unwrap();
+        (result, terminal.rendered())
+    }
+
+    fn typed(text: &str) -> Vec<Event> {
+        text.chars()
+            .map(|c| Event::Key(KeyEvent::from(KeyCode::Char(c))))
+            .collect()
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn edits_in_the_middle() {
+        let mut events = typed("git statxs");
+        events.extend([
+            key(KeyCode::Left, KeyModifiers::NONE),
+            key(KeyCode::Backspace, KeyModifiers::NONE),
+            key(KeyCode::Char('u'), KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+
+        let (result, _) = run(TextInput::new("Command"), events);
+        assert_eq!(result.as_deref(), Some("git status"));
+    }
+
+    #[test]
+    fn readline_word_keys() {
+        let mut events = vec![key(KeyCode::Char('w'), KeyModifiers::CONTROL)];
+        events.extend(typed("main"));
+        events.push(key(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        events.push(key(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        events.extend(typed("l"));
+        events.push(key(KeyCode::Enter, KeyModifiers::NONE));
+
+        let input = TextInput::new("Command").with_value("git checkout dev");
+        let (result, _) = run(input, events);
+        assert_eq!(result.as_deref(), Some("lit checkout main"));
+    }
+
+    #[test]
+    fn browses_history_and_restores_draft() {
+        let events = vec![
+            key(KeyCode::Up, KeyModifiers::NONE),
+            key(KeyCode::Up, KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ];
+        let input = TextInput::new("Search").with_history(vec!["ls".into(), "pwd".into()]);
+        assert_eq!(run(input, events).0.as_deref(), Some("ls"));
+
+        let mut events = typed("draft");
+        events.extend([
+            key(KeyCode::Up, KeyModifiers::NONE),
+            key(KeyCode::Down, KeyModifiers::NONE),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+        ]);
+        let input = TextInput::new("Search").with_history(vec!["ls".into()]);
+        assert_eq!(run(input, events).0.as_deref(), Some("draft"));
+    }
+
+    #[test]
+    fn validation_blocks_submit_and_shows_error() {
+        let validate = |v: &str| {
+            if v.is_empty() {
+                Err("Name can't be empty".to_string())
+            } else {
+                Ok(())
+            }
+        };
+        let events = vec![
+            key(KeyCode::Enter, KeyModifiers::NONE),
+            key(KeyCode::Esc, KeyModifiers::NONE),
+        ];
+
+        let (result, rendered) = run(TextInput::new("Name").with_validation(validate), events);
+        assert_eq!(result, None);
+        assert_eq!(rendered[1], "  Name can't be empty");
+    }
+
+    #[test]
+    fn renders_placeholder() {
+        let input = TextInput::new("Name").with_placeholder("e.g. deploy");
+        let (_, rendered) = run(input, vec![key(KeyCode::Esc, KeyModifiers::NONE)]);
+        assert_eq!(rendered[0], "Name: e.g. deploy");
+    }
+
+    #[test]
+    fn plain_prompt_keeps_value_and_revalidates() {
+        let validate = |v: &str| {
+            if v.contains(' ') {
+                Err("No spaces".to_string())
+            } else {
+                Ok(())
+            }
+        };
+        let mut input = TextInput::new("Name").with_validation(validate);
+        let mut output = Vec::new();
+
+        let result = input
+            .run_plain(&mut "bad name\ngood\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(result.as_deref(), Some("good"));
+        assert!(String::from_utf8(output).unwrap().contains("No spaces"));
+
+        let mut input = TextInput::new("Name").with_value("kept");
+        let result = input.run_plain(&mut "\n".as_bytes(), &mut Vec::new());
+        assert_eq!(result.unwrap().as_deref(), Some("kept"));
+    }
+}
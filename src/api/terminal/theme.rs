@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Styles shared by every widget, so they can be swapped out together.
+#[derive(Clone, Debug)]
+pub(crate) struct Theme {
+    pub(crate) header: Style,
+    /// Search prompts and other small highlights
+    pub(crate) accent: Style,
+    /// Hints, unfocused buttons and secondary columns
+    pub(crate) muted: Style,
+    /// The row under the cursor
+    pub(crate) highlight: Style,
+    pub(crate) checked: Style,
+    /// Unchecked markers, and the rows they belong to
+    pub(crate) unchecked: Style,
+    /// Characters matched by a search query
+    pub(crate) matched: Style,
+    /// Rows inside a range being marked for toggling
+    pub(crate) range: Style,
+    /// The focused confirm button
+    pub(crate) confirm: Style,
+    /// The focused cancel button
+    pub(crate) cancel: Style,
+    /// Validation messages
+    pub(crate) error: Style,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "kebab-case")]
+enum Slot {
+    Header,
+    Accent,
+    Muted,
+    Highlight,
+    Checked,
+    Unchecked,
+    Matched,
+    Range,
+    Confirm,
+    Cancel,
+    Error,
+}
+
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// The `[theme]` section of the config file.
+///
+/// ```toml
+/// [theme]
+/// preset = "light"
+///
+/// [theme.styles]
+/// header = "blue bold"
+/// confirm = "white on green bold"
+/// ```
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct ThemeConfig {
+    preset: ThemePreset,
+    /// Replaces the preset's style for each listed slot
+    styles: HashMap<Slot, String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ThemeErrors {
+    #[error("Unknown style `{0}` in theme, try something like `yellow bold` or `white on #303030`")]
+    UnknownStyle(String),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::default())
+    }
+}
+
+impl Theme {
+    pub(crate) fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Self {
+                header: Style::new().fg(Color::Yellow).bold(),
+                accent: Style::new().fg(Color::Yellow),
+                muted: Style::new().fg(Color::DarkGray),
+                highlight: Style::new().bg(Color::DarkGray).bold(),
+                checked: Style::new().fg(Color::Green),
+                unchecked: Style::new().fg(Color::Gray).dim(),
+                matched: Style::new().fg(Color::Yellow).bold(),
+                range: Style::new().bg(Color::Blue),
+                confirm: Style::new().bg(Color::DarkGray).fg(Color::Green).bold(),
+                cancel: Style::new().bg(Color::DarkGray).fg(Color::Red).bold(),
+                error: Style::new().fg(Color::Red),
+            },
+            ThemePreset::Light => Self {
+                header: Style::new().fg(Color::Blue).bold(),
+                accent: Style::new().fg(Color::Blue),
+                muted: Style::new().fg(Color::DarkGray),
+                highlight: Style::new().bg(Color::Gray).fg(Color::Black).bold(),
+                checked: Style::new().fg(Color::Green),
+                unchecked: Style::new().fg(Color::DarkGray).dim(),
+                matched: Style::new().fg(Color::Magenta).bold(),
+                range: Style::new().bg(Color::LightBlue),
+                confirm: Style::new().bg(Color::Green).fg(Color::White).bold(),
+                cancel: Style::new().bg(Color::Red).fg(Color::White).bold(),
+                error: Style::new().fg(Color::Red),
+            },
+            ThemePreset::HighContrast => Self {
+                header: Style::new().fg(Color::White).bold().underlined(),
+                accent: Style::new().fg(Color::LightYellow).bold(),
+                muted: Style::new().fg(Color::White),
+                highlight: Style::new().bg(Color::White).fg(Color::Black).bold(),
+                checked: Style::new().fg(Color::LightGreen).bold(),
+                unchecked: Style::new().fg(Color::White),
+                matched: Style::new().fg(Color::LightYellow).bold().underlined(),
+                range: Style::new().reversed(),
+                confirm: Style::new().bg(Color::LightGreen).fg(Color::Black).bold(),
+                cancel: Style::new().bg(Color::LightRed).fg(Color::Black).bold(),
+                error: Style::new().fg(Color::LightRed).bold(),
+            },
+        }
+    }
+
+    /// Attributes only, for `NO_COLOR` (https://no-color.org).
+    pub(crate) fn plain() -> Self {
+        Self {
+            header: Style::new().bold(),
+            accent: Style::new().bold(),
+            muted: Style::new(),
+            highlight: Style::new().reversed(),
+            checked: Style::new().bold(),
+            unchecked: Style::new(),
+            matched: Style::new().bold(),
+            range: Style::new().underlined(),
+            confirm: Style::new().reversed().bold(),
+            cancel: Style::new().reversed().bold(),
+            error: Style::new().bold(),
+        }
+    }
+
+    /// The theme described by the config file. With `no_color` the preset is
+    /// ignored and any colors in the overrides are dropped.
+    pub(crate) fn from_config(config: &ThemeConfig, no_color: bool) -> Result<Self, ThemeErrors> {
+        let mut theme = if no_color {
+            Self::plain()
+        } else {
+            Self::preset(config.preset)
+        };
+
+        for (slot, style) in &config.styles {
+            let mut style = parse_style(style)?;
+            if no_color {
+                style.fg = None;
+                style.bg = None;
+            }
+            *theme.slot_mut(*slot) = style;
+        }
+
+        Ok(theme)
+    }
+
+    /// The theme from the user's config, honoring `NO_COLOR`.
+    pub(crate) fn current() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        // Bad styles are rejected when the config is loaded in `main`
+        Self::from_config(&crate::api::config::get().theme, no_color).unwrap_or_else(|_| {
+            if no_color {
+                Self::plain()
+            } else {
+                Self::default()
+            }
+        })
+    }
+
+    /// Check that every style in the config parses, so mistakes surface at
+    /// startup rather than silently falling back to the defaults.
+    pub(crate) fn validate(config: &ThemeConfig) -> Result<(), ThemeErrors> {
+        Self::from_config(config, false).map(|_| ())
+    }
+
+    fn slot_mut(&mut self, slot: Slot) -> &mut Style {
+        match slot {
+            Slot::Header => &mut self.header,
+            Slot::Accent => &mut self.accent,
+            Slot::Muted => &mut self.muted,
+            Slot::Highlight => &mut self.highlight,
+            Slot::Checked => &mut self.checked,
+            Slot::Unchecked => &mut self.unchecked,
+            Slot::Matched => &mut self.matched,
+            Slot::Range => &mut self.range,
+            Slot::Confirm => &mut self.confirm,
+            Slot::Cancel => &mut self.cancel,
+            Slot::Error => &mut self.error,
+        }
+    }
+}
+
+/// Parse styles like `yellow bold` or `white on #303030 underlined`: an
+/// optional foreground, `on` followed by a background, and any modifiers.
+fn parse_style(input: &str) -> Result<Style, ThemeErrors> {
+    let unknown = || ThemeErrors::UnknownStyle(input.to_string());
+
+    let mut style = Style::new();
+    let mut tokens = input.split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        let modifier = match token.to_lowercase().as_str() {
+            "bold" => Some(Modifier::BOLD),
+            "dim" => Some(Modifier::DIM),
+            "italic" => Some(Modifier::ITALIC),
+            "underlined" | "underline" => Some(Modifier::UNDERLINED),
+            "reversed" | "reverse" => Some(Modifier::REVERSED),
+            "crossed-out" | "strikethrough" => Some(Modifier::CROSSED_OUT),
+            _ => None,
+        };
+
+        if let Some(modifier) = modifier {
+            style = style.add_modifier(modifier);
+        } else if token.eq_ignore_ascii_case("on") {
+            let color = tokens.next().ok_or_else(unknown)?;
+            style = style.bg(color.parse().map_err(|_| unknown())?);
+        } else {
+            style = style.fg(token.parse().map_err(|_| unknown())?);
+        }
+    }
+
+    Ok(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_styles() {
+        assert_eq!(
+            parse_style("yellow bold").unwrap(),
+            Style::new().fg(Color::Yellow).bold()
+        );
+        assert_eq!(
+            parse_style("white on #303030").unwrap(),
+            Style::new()
+                .fg(Color::White)
+                .bg(Color::Rgb(0x30, 0x30, 0x30))
+        );
+        assert_eq!(
+            parse_style("on dark-gray").unwrap(),
+            Style::new().bg(Color::DarkGray)
+        );
+        assert!(parse_style("white on").is_err());
+        assert!(parse_style("sparkly").is_err());
+    }
+
+    #[test]
+    fn config_overrides_preset() {
+        let config: ThemeConfig = toml::from_str(
+            r#"
+            preset = "light"
+            [styles]
+            header = "red"
+            "#,
+        )
+        .unwrap();
+        let theme = Theme::from_config(&config, false).unwrap();
+
+        assert_eq!(theme.header, Style::new().fg(Color::Red));
+        assert_eq!(theme.checked, Theme::preset(ThemePreset::Light).checked);
+    }
+
+    #[test]
+    fn no_color_drops_colors() {
+        let config: ThemeConfig = toml::from_str(
+            r#"
+            [styles]
+            header = "red underlined"
+            "#,
+        )
+        .unwrap();
+        let theme = Theme::from_config(&config, true).unwrap();
+
+        assert_eq!(theme.header, Style::new().underlined());
+        assert_eq!(theme.highlight, Style::new().reversed());
+    }
+}
@@ -0,0 +1,133 @@
+use std::io::stdout;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crossterm::{ExecutableCommand, cursor, event::DisableMouseCapture, terminal};
+
+/// What a live [`super::InlineTerminal`] changed about the tty, kept where
+/// the panic hook and signal handler can undo it.
+#[derive(Clone, Copy)]
+pub(super) struct TtyState {
+    pub(super) fullscreen: bool,
+    pub(super) mouse: bool,
+    /// The row the inline viewport starts on, which is cleared from
+    pub(super) start_row: u16,
+}
+
+static ACTIVE: Mutex<Option<TtyState>> = Mutex::new(None);
+
+fn active() -> MutexGuard<'static, Option<TtyState>> {
+    // A panic while the lock is held mustn't stop the panic hook restoring
+    ACTIVE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Record that a terminal has put the tty into raw mode.
+pub(super) fn claim(state: TtyState) {
+    *active() = Some(state);
+}
+
+/// Follow the inline viewport when a resize moves it.
+pub(super) fn move_viewport(start_row: u16) {
+    if let Some(state) = active().as_mut() {
+        state.start_row = start_row;
+    }
+}
+
+/// Put the tty back the way it was before the live terminal took it over.
+///
+/// Only the first call after a [`claim`] does anything, so the terminal's
+/// own cleanup won't clear away a panic report printed after the hook
+/// already restored things.
+pub(super) fn restore() -> crate::Result<()> {
+    let Some(state) = active().take() else {
+        return Ok(());
+    };
+
+    terminal::disable_raw_mode()?;
+    if state.mouse {
+        stdout().execute(DisableMouseCapture)?;
+    }
+    stdout().execute(cursor::Show)?;
+    if state.fullscreen {
+        stdout().execute(terminal::LeaveAlternateScreen)?;
+        return Ok(());
+    }
+    stdout().execute(cursor::MoveTo(0, state.start_row))?;
+    stdout().execute(terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    Ok(())
+}
+
+/// SIGTERM or SIGHUP, once one arrives while a [`SignalGuard`] is alive.
+static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Holds off exiting on SIGTERM or SIGHUP until dropped, see
+/// [`defer_signals`].
+pub(crate) struct SignalGuard(());
+
+/// Hold off exiting on SIGTERM or SIGHUP while work that mustn't be cut off
+/// partway runs. The process exits once the last guard drops, and work that
+/// can stop early should watch [`signalled`] to do so.
+pub(crate) fn defer_signals() -> SignalGuard {
+    GUARDS.fetch_add(1, Ordering::SeqCst);
+    SignalGuard(())
+}
+
+impl Drop for SignalGuard {
+    fn drop(&mut self) {
+        if GUARDS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            exit_on_pending_signal();
+        }
+    }
+}
+
+/// Whether a signal is waiting on deferred work to finish.
+pub(super) fn signalled() -> bool {
+    PENDING_SIGNAL.load(Ordering::SeqCst) != 0
+}
+
+/// Exit the way a pending signal would have, with the terminal restored.
+fn exit_on_pending_signal() {
+    let signal = PENDING_SIGNAL.load(Ordering::SeqCst);
+    if signal != 0 {
+        let _ = restore();
+        std::process::exit(128 + signal);
+    }
+}
+
+/// Install color-eyre's error and panic reports, restoring the terminal
+/// before a panic is reported, and on SIGTERM or SIGHUP before exiting.
+pub(crate) fn install_hooks() -> crate::Result<()> {
+    let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+
+    let panic_hook = panic_hook.into_panic_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        panic_hook(info);
+    }));
+
+    #[cfg(unix)]
+    watch_signals()?;
+    Ok(())
+}
+
+/// Exit the way the signal would have, but with the terminal restored first,
+/// and only once any [`defer_signals`] work has finished or rolled back.
+/// Ctrl+C never arrives as a signal while a widget has the tty in raw mode,
+/// the widgets read it as a key and cancel instead.
+#[cfg(unix)]
+fn watch_signals() -> crate::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGTERM};
+
+    let mut signals = signal_hook::iterator::Signals::new([SIGTERM, SIGHUP])?;
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            PENDING_SIGNAL.store(signal, Ordering::SeqCst);
+            if GUARDS.load(Ordering::SeqCst) == 0 {
+                exit_on_pending_signal();
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,478 @@
+//! Encrypted archive for history entries that were pruned from a history file.
+//!
+//! The archive is an age file protected by a passphrase. Its plaintext is one
+//! record per line: `<archived at>\t<source file>\t<command>`, with
+//! backslashes, tabs and newlines escaped.
+use std::io::{Read, Write};
+
+use age::secrecy::SecretString;
+
+use crate::api::terminal::{Column, Progress, SelectList, SelectResult, SelectTable, Theme};
+
+use super::{HistoryEntry, HistoryFormat, format_time, read_entries, replace_entries};
+
+const PASSPHRASE_ENV: &str = "UTILS_ARCHIVE_PASSPHRASE";
+
+#[derive(clap::Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct Arguments {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+    /// The path of your command history file
+    file: Option<String>,
+    #[clap(long)]
+    /// Preselect commands matching these patterns for archiving
+    exclude: Option<Vec<String>>,
+    #[clap(long)]
+    /// Path of the archive, defaults to $XDG_DATA_HOME/utils/history.age
+    archive: Option<String>,
+    #[clap(long, short)]
+    /// Archive everything matching --exclude without review (bypasses TUI)
+    yes: bool,
+}
+
+#[derive(clap::Subcommand)]
+pub(crate) enum Commands {
+    /// Decrypt the archive and print entries matching a pattern
+    Search {
+        /// Regex to match archived commands against, lists everything if omitted
+        pattern: Option<String>,
+        #[clap(long)]
+        /// Path of the archive, defaults to $XDG_DATA_HOME/utils/history.age
+        archive: Option<String>,
+    },
+    /// Move archived entries back into a history file
+    Restore {
+        /// The path of the history file to restore into
+        file: String,
+        /// Regex to match archived commands against, lists everything if omitted
+        pattern: Option<String>,
+        #[clap(long)]
+        /// Path of the archive, defaults to $XDG_DATA_HOME/utils/history.age
+        archive: Option<String>,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ArchiveErrors {
+    #[error("Could not find a data directory, pass --archive explicitly")]
+    MissingDataDir,
+    #[error("The passphrases did not match")]
+    PassphraseMismatch,
+    #[error("Could not decrypt the archive, is the passphrase correct?")]
+    Decrypt(#[from] age::DecryptError),
+    #[error("The archive is corrupted at line {0}")]
+    Corrupted(usize),
+    #[error("requires a history file or a command")]
+    MissingFile,
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    match &args.command {
+        Some(Commands::Search { pattern, archive }) => search(pattern, archive),
+        Some(Commands::Restore {
+            file,
+            pattern,
+            archive,
+        }) => restore(file, pattern, archive),
+        None => {
+            let Some(file) = &args.file else {
+                return Err(ArchiveErrors::MissingFile.into());
+            };
+            archive_history(file, &args.exclude, &args.archive, args.yes)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ArchivedEntry {
+    archived_at: i64,
+    source: String,
+    command: String,
+}
+
+impl ArchivedEntry {
+    fn new(source: &str, command: String) -> Self {
+        Self {
+            archived_at: chrono::Utc::now().timestamp(),
+            source: source.to_string(),
+            command,
+        }
+    }
+}
+
+/// Append `commands` removed from `source` to the archive at `archive`.
+///
+/// Used by `history rank --archive` so that deleted entries end up here
+/// instead of being dropped.
+pub(super) fn append(
+    archive: &Option<String>,
+    source: &str,
+    commands: Vec<String>,
+) -> crate::Result<usize> {
+    if commands.is_empty() {
+        return Ok(0);
+    }
+
+    let path = archive_path(archive)?;
+    let passphrase = passphrase(!path.exists())?;
+    let count = commands.len();
+    let source = source.to_string();
+
+    // Both ends of the round trip run scrypt, which takes a moment
+    Progress::spinner("Updating archive")
+        .with_summary(format!("Added {count} commands to {}", path.display()))
+        .show({
+            let path = path.clone();
+            move |progress| {
+                progress.message("decrypting");
+                let mut entries = load(&path, &passphrase)?;
+                entries.extend(commands.into_iter().map(|c| ArchivedEntry::new(&source, c)));
+                progress.message("encrypting");
+                store(&path, &passphrase, &entries)
+            }
+        })?;
+
+    Ok(count)
+}
+
+fn archive_history(
+    file: &str,
+    exclude: &Option<Vec<String>>,
+    archive: &Option<String>,
+    yes: bool,
+) -> crate::Result<()> {
+    let path = std::path::PathBuf::from(file);
+    let entries = read_entries(&path)?;
+
+    let exclude = exclude.as_ref().unwrap_or(&Vec::new()).to_owned();
+    let exclude = regex::RegexSet::new(exclude.iter().as_ref())?;
+
+    let remove: Vec<HistoryEntry> = if yes {
+        entries
+            .into_iter()
+            .filter(|e| exclude.is_match(&e.command))
+            .collect()
+    } else {
+        let list_height = (entries.len() as u16).min(20) + 5;
+        let mut select = SelectList::new(
+            entries,
+            "Deselect commands to archive",
+            |e: &HistoryEntry| e.command.replace('\n', " ⏎ "),
+        )
+        .with_confirm("Archive", "Cancel")
+        .with_toggleable();
+
+        let to_deselect: Vec<usize> = select
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| exclude.is_match(&e.command))
+            .map(|(i, _)| i)
+            .collect();
+        for i in to_deselect {
+            select.set_selected(i, false);
+        }
+
+        // The selected entries are the ones kept
+        let SelectResult::Many(kept) = select.prompt(list_height, 120)? else {
+            println!("Cancelled.");
+            return Ok(());
+        };
+        select
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| kept.binary_search(i).is_err())
+            .map(|(_, e)| e.clone())
+            .collect()
+    };
+
+    if remove.is_empty() {
+        println!("Nothing to archive.");
+        return Ok(());
+    }
+
+    // Archive first so a failure here never loses the removed entries
+    let count = append(
+        archive,
+        file,
+        remove.iter().map(|e| e.command.clone()).collect(),
+    )?;
+
+    let changes: Vec<_> = remove.iter().map(|e| (e, None)).collect();
+    replace_entries(&path, &changes)?;
+
+    println!("Archived {count} commands from {}", path.display());
+    Ok(())
+}
+
+fn search(pattern: &Option<String>, archive: &Option<String>) -> crate::Result<()> {
+    let pattern = regex::Regex::new(pattern.as_deref().unwrap_or(""))?;
+    let path = archive_path(archive)?;
+    let entries = decrypt(&path, &passphrase(false)?)?;
+
+    for entry in entries.iter().filter(|e| pattern.is_match(&e.command)) {
+        let time = format_time(entry.archived_at).unwrap_or_default();
+        println!(
+            "{time}  {}  {}",
+            entry.source,
+            entry.command.replace('\n', " ⏎ ")
+        );
+    }
+
+    Ok(())
+}
+
+fn restore(file: &str, pattern: &Option<String>, archive: &Option<String>) -> crate::Result<()> {
+    let pattern = regex::Regex::new(pattern.as_deref().unwrap_or(""))?;
+    let path = archive_path(archive)?;
+    let passphrase = passphrase(false)?;
+    let entries = decrypt(&path, &passphrase)?;
+
+    let (matching, rest): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| pattern.is_match(&e.command));
+
+    if matching.is_empty() {
+        println!("No archived commands match.");
+        return Ok(());
+    }
+
+    // One more row than a list for the column titles
+    let list_height = (matching.len() as u16).min(20) + 6;
+    let muted = Theme::current().muted;
+    let columns = vec![
+        Column::new("Command", |e: &ArchivedEntry| {
+            e.command.replace('\n', " ⏎ ")
+        }),
+        Column::new("Archived", |e: &ArchivedEntry| {
+            format_time(e.archived_at).unwrap_or_default()
+        })
+        .with_sort_key(|e: &ArchivedEntry| e.archived_at)
+        .with_style(muted),
+        Column::new("From", |e: &ArchivedEntry| e.source.clone()).with_style(muted),
+    ];
+    let mut select = SelectTable::table(matching, "Select commands to restore", columns)
+        .with_confirm("Restore", "Cancel")
+        .with_toggleable();
+
+    let result = select.prompt(list_height, 120)?;
+
+    if let SelectResult::Cancelled = result {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let restored: Vec<_> = select.selected_items().into_iter().cloned().collect();
+    let remaining: Vec<_> = rest
+        .into_iter()
+        .chain(select.unselected_items().into_iter().cloned())
+        .collect();
+
+    let history = std::path::Path::new(file);
+    let format = match history.exists() {
+        true => HistoryFormat::detect(&read_entries(history)?),
+        false => HistoryFormat::Plain,
+    };
+    let now = chrono::Utc::now().timestamp();
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    for entry in &restored {
+        writeln!(out, "{}", format.entry(&entry.command, now))?;
+    }
+    store(&path, &passphrase, &remaining)?;
+
+    println!("Restored {} commands to {file}", restored.len());
+    Ok(())
+}
+
+fn archive_path(archive: &Option<String>) -> crate::Result<std::path::PathBuf> {
+    if let Some(archive) = archive {
+        return Ok(archive.into());
+    }
+
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| std::path::Path::new(&h).join(".local/share")))
+        .ok_or(ArchiveErrors::MissingDataDir)?;
+
+    Ok(data_dir.join("utils").join("history.age"))
+}
+
+/// Read the passphrase from the environment, or prompt for it. New archives
+/// ask for the passphrase twice.
+fn passphrase(new: bool) -> crate::Result<SecretString> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase.into());
+    }
+
+    let passphrase = rpassword::prompt_password("Archive passphrase: ")?;
+    if new && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        return Err(ArchiveErrors::PassphraseMismatch.into());
+    }
+
+    Ok(passphrase.into())
+}
+
+/// [`load`] behind a spinner, for when nothing else is on screen yet.
+fn decrypt(path: &std::path::Path, passphrase: &SecretString) -> crate::Result<Vec<ArchivedEntry>> {
+    let path = path.to_path_buf();
+    let passphrase = passphrase.clone();
+    Progress::spinner("Decrypting archive").show(move |_| load(&path, &passphrase))
+}
+
+fn load(path: &std::path::Path, passphrase: &SecretString) -> crate::Result<Vec<ArchivedEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let encrypted = std::fs::read(path)?;
+    let decryptor = age::Decryptor::new_buffered(&encrypted[..]).map_err(ArchiveErrors::from)?;
+    let identity = age::scrypt::Identity::new(passphrase.clone());
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as _))
+        .map_err(ArchiveErrors::from)?;
+
+    let mut plaintext = String::new();
+    reader.read_to_string(&mut plaintext)?;
+
+    plaintext
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(archived_at), Some(source), Some(command)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(ArchiveErrors::Corrupted(i + 1).into());
+            };
+
+            Ok(ArchivedEntry {
+                archived_at: archived_at
+                    .parse()
+                    .map_err(|_| ArchiveErrors::Corrupted(i + 1))?,
+                source: unescape(source),
+                command: unescape(command),
+            })
+        })
+        .collect()
+}
+
+fn store(
+    path: &std::path::Path,
+    passphrase: &SecretString,
+    entries: &[ArchivedEntry],
+) -> crate::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut plaintext = String::new();
+    for entry in entries {
+        plaintext.push_str(&format!(
+            "{}\t{}\t{}\n",
+            entry.archived_at,
+            escape(&entry.source),
+            escape(&entry.command)
+        ));
+    }
+
+    // Write next to the archive and swap it in, so an interrupted write can't
+    // destroy the existing archive
+    let tmp_path = path.with_extension("age.tmp");
+    let encryptor = age::Encryptor::with_user_passphrase(passphrase.clone());
+    let mut writer = encryptor.wrap_output(std::fs::File::create(&tmp_path)?)?;
+    writer.write_all(plaintext.as_bytes())?;
+    writer.finish()?;
+    std::fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(contents: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn escaping_round_trips() {
+        for text in ["ls", "a\tb\nc", "echo \\n\\t \\", "\\\\\n"] {
+            let escaped = escape(text);
+            assert!(!escaped.contains(['\t', '\n']));
+            assert_eq!(unescape(&escaped), text);
+        }
+    }
+
+    #[test]
+    fn removing_entries_keeps_every_other_line() {
+        let file = history(": 100:0;ls\n\n: 200:0;echo a\\\nb\n: 300:0;pwd\n#400\n");
+        let entries = read_entries(file.path()).unwrap();
+
+        replace_entries(file.path(), &[(&entries[1], None)]).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            ": 100:0;ls\n\n: 300:0;pwd\n#400\n"
+        );
+    }
+
+    #[test]
+    fn restores_in_each_history_format() {
+        let cases = [
+            (": 100:0;ls\n", HistoryFormat::Zsh),
+            ("#100\nls\n", HistoryFormat::Bash),
+            ("ls\n", HistoryFormat::Plain),
+        ];
+        for (contents, expected) in cases {
+            let file = history(contents);
+            let format = HistoryFormat::detect(&read_entries(file.path()).unwrap());
+            assert_eq!(format, expected);
+
+            let mut out = std::fs::OpenOptions::new()
+                .append(true)
+                .open(file.path())
+                .unwrap();
+            writeln!(out, "{}", format.entry("echo a\necho b", 200)).unwrap();
+            writeln!(out, "{}", format.entry("pwd", 300)).unwrap();
+
+            let entries = read_entries(file.path()).unwrap();
+            let commands: Vec<_> = entries.iter().map(|e| e.command.as_str()).collect();
+            assert_eq!(commands, ["ls", "echo a\necho b", "pwd"]);
+            if format != HistoryFormat::Plain {
+                assert_eq!(entries[1].timestamp, Some(200));
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::mpsc;
+
+use crate::api::terminal::{Column, Feed, SelectTable, TextInput, Theme};
+
+use super::{HistoryEntry, HistoryFormat, format_time, read_entries, replace_entries};
+
+/// How many entries to search between updates to the list
+const SEARCH_CHUNK: usize = 2000;
+
+#[derive(clap::Args)]
+pub(crate) struct Arguments {
+    /// The path of your command history file
+    file: String,
+    /// Regex to find the command with, asked for if omitted
+    pattern: Option<String>,
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    let path = std::path::PathBuf::from(&args.file);
+    let entries = read_entries(&path)?;
+
+    let pattern = match &args.pattern {
+        Some(pattern) => pattern.clone(),
+        None => {
+            let mut input = TextInput::new("Search")
+                .with_placeholder("regex, leave empty to list everything")
+                .with_validation(|value| {
+                    regex::Regex::new(value)
+                        .map(|_| ())
+                        .map_err(|_| "Not a valid regex".to_string())
+                });
+            match input.prompt(120)? {
+                Some(pattern) => pattern,
+                None => {
+                    println!("Cancelled.");
+                    return Ok(());
+                }
+            }
+        }
+    };
+    let pattern = regex::Regex::new(&pattern)?;
+
+    // Most recent first, since that's usually the one that needs fixing
+    let newest_first: Vec<HistoryEntry> = entries.iter().rev().cloned().collect();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || search(&newest_first, &pattern, &tx));
+
+    let (found, finished) = first_matches(&rx);
+    if found.is_empty() {
+        println!("No commands match.");
+        return Ok(());
+    }
+    // More matches may still be coming when the search hasn't finished
+    let rows = if finished { found.len() } else { 20 };
+    let list_height = (rows as u16).min(20) + 6;
+    let muted = Theme::current().muted;
+    let columns = vec![
+        Column::new("Line", |e: &HistoryEntry| e.line.to_string())
+            .with_sort_key(|e: &HistoryEntry| e.line)
+            .right_aligned()
+            .with_style(muted),
+        Column::new("Time", |e: &HistoryEntry| {
+            e.timestamp.and_then(format_time).unwrap_or_default()
+        })
+        .with_sort_key(|e: &HistoryEntry| e.timestamp)
+        .with_style(muted),
+        Column::new("Command", |e: &HistoryEntry| e.command.replace('\n', " ⏎ ")),
+    ];
+    let mut select = SelectTable::table(found, "Pick a command to edit", columns).with_feed(rx);
+
+    let Some(picked) = select.pick_one(list_height, 120)?.map(|e| e.line) else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+    let Some(index) = entries.iter().position(|e| e.line == picked) else {
+        return Ok(());
+    };
+
+    // The other matches are handy to pull bits from with ↑/↓
+    let history = select
+        .items()
+        .iter()
+        .rev()
+        .filter(|e| e.line != picked)
+        .map(|e| e.command.clone())
+        .collect();
+    let mut input = TextInput::new("Edit")
+        .with_value(entries[index].command.clone())
+        .with_history(history)
+        .with_validation(|value| {
+            if value.trim().is_empty() {
+                Err("The command can't be empty".to_string())
+            } else {
+                Ok(())
+            }
+        });
+
+    let Some(command) = input.prompt(120)? else {
+        println!("Cancelled.");
+        return Ok(());
+    };
+
+    let entry = &entries[index];
+    if command == entry.command {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    replace_entries(&path, &[(entry, Some(replace_command(entry, &command)))])?;
+
+    println!("Updated line {} of {}", entry.line, path.display());
+    Ok(())
+}
+
+/// Wait for the search to find something, returning the first matches and
+/// whether the search has finished.
+fn first_matches(rx: &mpsc::Receiver<Feed<HistoryEntry>>) -> (Vec<HistoryEntry>, bool) {
+    let mut found = Vec::new();
+    loop {
+        // Once something's found, take only what's already been sent
+        let next = match found.is_empty() {
+            true => rx.recv().map_err(|_| mpsc::TryRecvError::Disconnected),
+            false => rx.try_recv(),
+        };
+        match next {
+            Ok(Feed::Items(items)) => found.extend(items),
+            Ok(Feed::Status(_)) => {}
+            Err(mpsc::TryRecvError::Empty) => return (found, false),
+            // The search hangs up once it's been through every entry
+            Err(mpsc::TryRecvError::Disconnected) => return (found, true),
+        }
+    }
+}
+
+/// Feed the entries matching `pattern` to the list a chunk at a time, so
+/// the first matches show up before a long history has been searched.
+fn search(entries: &[HistoryEntry], pattern: &regex::Regex, tx: &mpsc::Sender<Feed<HistoryEntry>>) {
+    let total = entries.len();
+    let mut found = 0;
+    for (n, chunk) in entries.chunks(SEARCH_CHUNK).enumerate() {
+        let matches: Vec<_> = chunk
+            .iter()
+            .filter(|e| pattern.is_match(&e.command))
+            .cloned()
+            .collect();
+        found += matches.len();
+
+        let searched = ((n + 1) * SEARCH_CHUNK).min(total);
+        let status = format!("Searching… {searched}/{total}");
+        // Sending fails once the list has been answered and dropped
+        if tx.send(Feed::Items(matches)).is_err() || tx.send(Feed::Status(Some(status))).is_err() {
+            return;
+        }
+    }
+
+    let status = (found == 0).then(|| "No commands match".to_string());
+    let _ = tx.send(Feed::Status(status));
+}
+
+/// Swap the command in an entry's raw text, keeping any timestamp metadata
+/// in front of it.
+fn replace_command(entry: &HistoryEntry, command: &str) -> String {
+    let format = HistoryFormat::of(entry);
+    let prefix = entry
+        .raw
+        .strip_suffix(&format.encode(&entry.command))
+        .unwrap_or_default();
+    format!("{prefix}{}", format.encode(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(raw: &str, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            line: 1,
+            timestamp: None,
+            command: command.to_string(),
+            raw: raw.to_string(),
+        }
+    }
+
+    #[test]
+    fn replacing_keeps_timestamps() {
+        let zsh = entry(": 1700000000:0;git push", "git push");
+        assert_eq!(
+            replace_command(&zsh, "git push -u origin"),
+            ": 1700000000:0;git push -u origin"
+        );
+
+        let bash = entry("#1700000000\nls", "ls");
+        assert_eq!(replace_command(&bash, "ls -la"), "#1700000000\nls -la");
+        let bash = entry(
+            "#1700000000\nfor f in *\ndo ls; done",
+            "for f in *\ndo ls; done",
+        );
+        assert_eq!(replace_command(&bash, "ls\npwd"), "#1700000000\nls\npwd");
+
+        let multiline = entry(": 1700000000:0;echo a\\\necho b", "echo a\necho b");
+        assert_eq!(
+            replace_command(&multiline, "echo c"),
+            ": 1700000000:0;echo c"
+        );
+    }
+
+    #[test]
+    fn editing_swaps_only_the_entry_lines() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), ": 1:0;ls\n\n: 2:0;echo a\\\nb\n: 3:0;pwd").unwrap();
+        let entries = read_entries(file.path()).unwrap();
+
+        let raw = replace_command(&entries[1], "echo c");
+        replace_entries(file.path(), &[(&entries[1], Some(raw))]).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            ": 1:0;ls\n\n: 2:0;echo c\n: 3:0;pwd"
+        );
+    }
+}
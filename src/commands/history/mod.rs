@@ -0,0 +1,551 @@
+use std::io::{BufRead, Write};
+
+use ratatui::text::{Line, Span, Text};
+
+use crate::api::terminal::{
+    Change, ConfirmPrompt, PreviewPosition, SelectList, SelectResult, Theme, defer_signals,
+};
+
+mod archive;
+mod edit;
+mod session;
+
+#[derive(clap::Args)]
+pub(crate) struct Arguments {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+    args: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+#[clap(arg_required_else_help = true)]
+pub(crate) enum Commands {
+    /// Sort your command history by most to least used. Will dedupe.
+    Rank {
+        /// The path of your command history file
+        file: String,
+        #[clap(long)]
+        /// Whether to overwrite the original file (bypasses TUI, but asks to
+        /// confirm the changes)
+        save: bool,
+        #[clap(long, short)]
+        /// Save without confirming the changes first
+        yes: bool,
+        #[clap(long)]
+        /// Remove these commands when constructing the new command history
+        exclude: Option<Vec<String>>,
+        #[clap(long, num_args = 0..=1, require_equals = true, value_name = "PATH")]
+        /// Move removed commands into an encrypted archive instead of dropping
+        /// them, optionally at a specific path given as `--archive=PATH`
+        archive: Option<Option<String>>,
+        #[clap(long)]
+        /// Use the whole terminal for the list instead of drawing it inline
+        fullscreen: bool,
+        #[clap(long)]
+        /// Group the list by the program each command runs
+        group: bool,
+    },
+    /// Pull a contiguous slice of history and export it as a script or runbook
+    Session(session::Arguments),
+    /// Move commands into an encrypted archive, or search and restore them
+    Archive(archive::Arguments),
+    /// Search for a command and fix it up in place
+    Edit(edit::Arguments),
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    let Some(commands) = &args.command else {
+        return Err(color_eyre::eyre::eyre!("requires a command"));
+    };
+
+    match commands {
+        Commands::Rank {
+            file,
+            save,
+            yes,
+            exclude,
+            archive,
+            fullscreen,
+            group,
+        } => rank(file, *save, *yes, exclude, archive, *fullscreen, *group),
+        Commands::Session(args) => session::run(args),
+        Commands::Archive(args) => archive::run(args),
+        Commands::Edit(args) => edit::run(args),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CommandStatus {
+    Save,
+    Delete,
+}
+
+#[derive(Debug, Clone)]
+struct Command {
+    raw: String,
+    status: CommandStatus,
+    /// How many times the command appears in the history file
+    count: usize,
+    /// 1-based line numbers of every occurrence
+    lines: Vec<usize>,
+}
+
+impl Command {
+    fn new(raw: String, status: CommandStatus, line: usize) -> Self {
+        Self {
+            raw,
+            status,
+            count: 1,
+            lines: vec![line],
+        }
+    }
+}
+
+fn rank(
+    file: &String,
+    save: bool,
+    yes: bool,
+    exclude: &Option<Vec<String>>,
+    archive: &Option<Option<String>>,
+    fullscreen: bool,
+    group: bool,
+) -> crate::Result<()> {
+    let path = std::path::PathBuf::from(file);
+    let file = std::fs::File::open(&path)?;
+    let mut reader = std::io::BufReader::new(file);
+
+    let mut line = String::new();
+    let mut line_number = 0;
+    let mut commands = Vec::<Command>::new();
+
+    let exclude = exclude.as_ref().unwrap_or(&Vec::new()).to_owned();
+    let exclude = regex::RegexSet::new(exclude.iter().as_ref())?;
+
+    while reader.read_line(&mut line)? > 0 {
+        line_number += 1;
+
+        if exclude.is_match(&line) {
+            commands.push(Command::new(
+                line.trim_end().to_string(),
+                CommandStatus::Delete,
+                line_number,
+            ));
+            line.clear();
+            continue;
+        }
+
+        // This lookup is linear. We _could_ use a hash map to memoize this if
+        // it gets slow. Tradeoff being memory usage.
+        match commands.iter().position(|c| c.raw == line.trim_end()) {
+            Some(i) => {
+                commands[i].count += 1;
+                commands[i].lines.push(line_number);
+                commands.swap(i, i / 2);
+            }
+            None => commands.push(Command::new(
+                line.trim_end().to_string(),
+                CommandStatus::Save,
+                line_number,
+            )),
+        };
+
+        line.clear();
+    }
+
+    if save {
+        let (commands, deleted): (Vec<_>, Vec<_>) = commands
+            .into_iter()
+            .partition(|c| matches!(c.status, CommandStatus::Save));
+
+        if !yes && !confirm_save(&path, line_number, &commands, &deleted, archive)? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        archive_deleted(archive, &path.to_string_lossy(), &deleted)?;
+
+        // nushell reads from the end, so reverse the list (higher priority
+        // commands) should be at the end of the file
+        let commands = commands.into_iter().rev().collect::<Vec<_>>();
+        write_history(&path, &commands)?;
+    } else {
+        let list_height = (commands.len() as u16).min(20) + 5;
+        let muted = Theme::current().muted;
+        let mut select = SelectList::new(commands, "Ranked History", |s: &Command| s.raw.clone())
+            .with_column(move |s: &Command| Span::styled(format!("{}×", s.count), muted))
+            .with_preview(PreviewPosition::Right, move |s: &Command| {
+                preview_command(s, muted)
+            })
+            .with_confirm("Save", "Cancel")
+            .with_toggleable()
+            .with_fullscreen(fullscreen);
+        if group {
+            select = select.with_groups(|s: &Command| program(&s.raw));
+        }
+
+        // Pre-deselect items marked for deletion (matched by --exclude)
+        let to_deselect: Vec<usize> = select
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(_, cmd)| matches!(cmd.status, CommandStatus::Delete))
+            .map(|(i, _)| i)
+            .collect();
+        for i in to_deselect {
+            select.set_selected(i, false);
+        }
+
+        let SelectResult::Many(indices) = select.prompt(list_height, 120)? else {
+            println!("Cancelled.");
+            return Ok(());
+        };
+        let commands = select.items();
+
+        // Same as above, need to reverse the list on save.
+        let items: Vec<_> = indices.iter().rev().map(|&i| commands[i].clone()).collect();
+
+        let deleted: Vec<_> = (0..commands.len())
+            .filter(|i| indices.binary_search(i).is_err())
+            .map(|i| commands[i].clone())
+            .collect();
+        archive_deleted(archive, &path.to_string_lossy(), &deleted)?;
+
+        write_history(&path, &items)?;
+        println!("Saved {} commands to {}", items.len(), path.display());
+    }
+
+    Ok(())
+}
+
+/// Show what saving will do to the history file and ask to go ahead.
+fn confirm_save(
+    path: &std::path::Path,
+    lines: usize,
+    kept: &[Command],
+    deleted: &[Command],
+    archive: &Option<Option<String>>,
+) -> crate::Result<bool> {
+    let mut context = vec![Line::from(format!(
+        "  {lines} lines become {} commands, most used last",
+        kept.len()
+    ))];
+    if archive.is_some() && !deleted.is_empty() {
+        context.push(Line::from("  Removed commands are moved to the archive"));
+    }
+    let changes: Vec<Change> = deleted
+        .iter()
+        .map(|c| Change::Removed(c.raw.clone()))
+        .collect();
+
+    let mut prompt =
+        ConfirmPrompt::changes(format!("Rewrite {}?", path.display()), context, &changes);
+    prompt.prompt(prompt.height(20), 120)
+}
+
+/// The program a command runs, skipping any leading `VAR=value`
+/// assignments.
+fn program(command: &str) -> String {
+    command
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The full command, how often it was used and the lines it appeared on.
+fn preview_command(command: &Command, muted: ratatui::style::Style) -> Text<'static> {
+    let times = if command.count == 1 { "time" } else { "times" };
+    let lines = command
+        .lines
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut text = Text::from(vec![
+        Line::from(command.raw.clone()),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Used {} {times}", command.count),
+            muted,
+        )),
+        Line::from(Span::styled(format!("Lines {lines}"), muted)),
+    ]);
+    if matches!(command.status, CommandStatus::Delete) {
+        text.push_line(Line::from(Span::styled("Matched by --exclude", muted)));
+    }
+
+    text
+}
+
+/// Archive commands marked for deletion when `--archive` was passed, otherwise
+/// they are dropped.
+fn archive_deleted(
+    archive: &Option<Option<String>>,
+    source: &str,
+    deleted: &[Command],
+) -> crate::Result<()> {
+    let Some(archive) = archive else {
+        return Ok(());
+    };
+
+    let commands = deleted.iter().map(|c| c.raw.clone()).collect();
+    let count = archive::append(archive, source, commands)?;
+    if count > 0 {
+        println!("Archived {count} removed commands");
+    }
+
+    Ok(())
+}
+
+/// A single entry read from a history file, along with where it came from.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    /// 1-based line number of the entry in the history file
+    line: usize,
+    /// Unix timestamp, if the history format records one
+    timestamp: Option<i64>,
+    command: String,
+    /// The entry exactly as it appears in the file, so it can be written back
+    /// without losing metadata
+    raw: String,
+}
+
+impl HistoryEntry {
+    /// The 1-based lines of the history file `raw` was read from.
+    fn lines(&self) -> std::ops::RangeInclusive<usize> {
+        // bash's timestamp, and any blank lines after it, sit above the
+        // command
+        let lines = self.raw.split('\n').count();
+        let start = self.line - (lines - self.command.split('\n').count());
+        start..=start + lines - 1
+    }
+}
+
+/// How a history file lays out its entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HistoryFormat {
+    /// zsh extended history, `: <ts>:<duration>;<cmd>`
+    Zsh,
+    /// bash with `HISTTIMEFORMAT`, a `#<ts>` line before each command
+    Bash,
+    /// One command per line
+    Plain,
+}
+
+impl HistoryFormat {
+    /// The format `entry` was read in.
+    fn of(entry: &HistoryEntry) -> Self {
+        let first = entry.raw.split('\n').next().unwrap_or_default();
+        if parse_zsh_extended(first).is_some() {
+            Self::Zsh
+        } else if entry.raw.contains('\n') && bash_timestamp(first).is_some() {
+            Self::Bash
+        } else {
+            Self::Plain
+        }
+    }
+
+    /// The format of a history file going by its latest entry, plain when
+    /// there are none.
+    fn detect(entries: &[HistoryEntry]) -> Self {
+        entries.last().map_or(Self::Plain, Self::of)
+    }
+
+    /// `command` as it's written in this format, where only bash keeps
+    /// newlines as they are.
+    fn encode(self, command: &str) -> String {
+        match self {
+            Self::Bash => command.to_string(),
+            Self::Zsh | Self::Plain => command.replace('\n', "\\\n"),
+        }
+    }
+
+    /// A whole entry for `command`, run at `timestamp`.
+    fn entry(self, command: &str, timestamp: i64) -> String {
+        let command = self.encode(command);
+        match self {
+            Self::Zsh => format!(": {timestamp}:0;{command}"),
+            Self::Bash => format!("#{timestamp}\n{command}"),
+            Self::Plain => command,
+        }
+    }
+}
+
+/// Read every entry from a history file in order.
+///
+/// Understands zsh extended history (`: <ts>:<duration>;<cmd>`), bash history
+/// with `HISTTIMEFORMAT` (`#<ts>` lines preceding each command, which runs up
+/// to the next one) and plain one command per line files such as nushell's.
+fn read_entries(path: &std::path::Path) -> crate::Result<Vec<HistoryEntry>> {
+    let file = std::fs::File::open(path)?;
+    let reader = std::io::BufReader::new(file);
+
+    let mut entries = Vec::<HistoryEntry>::new();
+    let mut pending_timestamp: Option<(i64, String)> = None;
+    let mut continues = false;
+    let mut in_bash_entry = false;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        // zsh escapes embedded newlines with a trailing backslash, while
+        // bash keeps every line up to the next timestamp in the same entry
+        let joins = continues || (in_bash_entry && bash_timestamp(&line).is_none());
+        if joins && let Some(last) = entries.last_mut() {
+            let part = match continues {
+                true => line.trim_end_matches('\\'),
+                false => &line,
+            };
+            last.command.push('\n');
+            last.command.push_str(part);
+            last.raw.push('\n');
+            last.raw.push_str(&line);
+            continues = continues && line.ends_with('\\');
+            continue;
+        }
+
+        if let Some(ts) = bash_timestamp(&line) {
+            pending_timestamp = Some((ts, line));
+            in_bash_entry = false;
+            continue;
+        }
+
+        // A timestamp belongs to the next command, past any blank lines
+        if line.trim().is_empty()
+            && let Some((_, ts_lines)) = pending_timestamp.as_mut()
+        {
+            ts_lines.push('\n');
+            ts_lines.push_str(&line);
+            continue;
+        }
+
+        let (timestamp, command, raw) = match parse_zsh_extended(&line) {
+            Some((ts, command)) => (Some(ts), command, line.clone()),
+            None => match pending_timestamp.take() {
+                Some((ts, ts_lines)) => (Some(ts), line.as_str(), format!("{ts_lines}\n{line}")),
+                None => (None, line.as_str(), line.clone()),
+            },
+        };
+
+        if command.trim().is_empty() {
+            continue;
+        }
+
+        in_bash_entry = raw.contains('\n');
+        continues = !in_bash_entry && command.ends_with('\\');
+        let command = match continues {
+            true => command.trim_end_matches('\\'),
+            false => command,
+        };
+        entries.push(HistoryEntry {
+            line: i + 1,
+            timestamp,
+            command: command.to_string(),
+            raw,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// The timestamp of a bash `#<ts>` line.
+fn bash_timestamp(line: &str) -> Option<i64> {
+    line.strip_prefix('#')?.parse().ok()
+}
+
+fn parse_zsh_extended(line: &str) -> Option<(i64, &str)> {
+    let rest = line.strip_prefix(": ")?;
+    let (meta, command) = rest.split_once(';')?;
+    let (ts, _duration) = meta.split_once(':')?;
+    Some((ts.parse().ok()?, command))
+}
+
+fn format_time(ts: i64) -> Option<String> {
+    let time = chrono::DateTime::from_timestamp(ts, 0)?;
+    Some(
+        time.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+    )
+}
+
+/// Rewrite the history file at `path`, swapping the lines each of `changes`
+/// was read from for its new raw text, or dropping them for `None`. Every
+/// other line, blank or not, is written back as it was.
+fn replace_entries(
+    path: &std::path::Path,
+    changes: &[(&HistoryEntry, Option<String>)],
+) -> crate::Result<()> {
+    let original = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = original.split_inclusive('\n').collect();
+
+    let mut replaced = vec![None; lines.len()];
+    for (entry, raw) in changes {
+        for line in entry.lines() {
+            replaced[line - 1] = Some(None);
+        }
+        replaced[entry.lines().start() - 1] = Some(raw.as_deref());
+    }
+
+    let mut out = String::with_capacity(original.len());
+    for (i, line) in lines.iter().enumerate() {
+        match replaced[i] {
+            None => out.push_str(line),
+            Some(None) => {}
+            Some(Some(raw)) => {
+                out.push_str(raw);
+                out.push('\n');
+            }
+        }
+    }
+    // A signal mid-write would leave the history cut short
+    let _signals = defer_signals();
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
+fn write_history(path: &std::path::Path, commands: &[Command]) -> crate::Result<()> {
+    let _signals = defer_signals();
+    let mut file = std::fs::File::create(path)?;
+    for cmd in commands {
+        writeln!(file, "{}", cmd.raw)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser as _;
+
+    use super::*;
+
+    #[derive(clap::Parser)]
+    struct Cli {
+        #[clap(subcommand)]
+        command: Commands,
+    }
+
+    fn rank_archive(args: &[&str]) -> (String, Option<Option<String>>) {
+        let cli = Cli::try_parse_from([&["utils", "rank"], args].concat()).unwrap();
+        let Commands::Rank { file, archive, .. } = cli.command else {
+            panic!("expected rank");
+        };
+        (file, archive)
+    }
+
+    #[test]
+    fn archive_path_needs_an_equals_sign() {
+        assert_eq!(
+            rank_archive(&["--archive", "~/.zsh_history"]),
+            ("~/.zsh_history".to_string(), Some(None))
+        );
+        assert_eq!(
+            rank_archive(&["--archive=old.age", "~/.zsh_history"]),
+            (
+                "~/.zsh_history".to_string(),
+                Some(Some("old.age".to_string()))
+            )
+        );
+        assert_eq!(rank_archive(&["~/.zsh_history"]).1, None);
+    }
+}
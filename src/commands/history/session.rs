@@ -0,0 +1,444 @@
+use std::io::Write;
+
+use ratatui::text::{Line, Span, Text};
+
+use crate::api::terminal::{PreviewPosition, SelectList, SelectResult, Theme};
+
+use super::{HistoryEntry, format_time, read_entries};
+
+#[derive(clap::Args)]
+pub(crate) struct Arguments {
+    /// The path of your command history file
+    file: String,
+    #[clap(long)]
+    /// Start of the session, e.g. `2026-01-31 14:05`, `14:05` or a unix timestamp
+    from: Option<String>,
+    #[clap(long)]
+    /// End of the session, same formats as --from
+    to: Option<String>,
+    #[clap(long, conflicts_with_all = ["from", "to"])]
+    /// Line range of the session instead of a time range, e.g. `120-180`
+    lines: Option<String>,
+    #[clap(long, value_enum, default_value_t = Format::Bash)]
+    /// What to export the session as
+    format: Format,
+    #[clap(long, default_value = "session")]
+    /// Title of the runbook, or name of the justfile recipe
+    name: String,
+    #[clap(long, short)]
+    /// Where to write the export, defaults to stdout
+    output: Option<String>,
+    #[clap(long, short)]
+    /// Export the whole slice without pruning it first (bypasses TUI)
+    yes: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum Format {
+    /// A `#!/usr/bin/env bash` script
+    Bash,
+    /// A Markdown runbook with one code block per command
+    Markdown,
+    /// A justfile recipe that runs the commands as one bash script
+    Just,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum SessionErrors {
+    #[error("Could not understand time `{0}`, try `YYYY-MM-DD HH:MM`, `HH:MM` or a unix timestamp")]
+    InvalidTime(String),
+    #[error("Could not understand line range `{0}`, try `START-END`")]
+    InvalidLineRange(String),
+    #[error("The history file has no timestamps, use --lines instead")]
+    MissingTimestamps,
+    #[error("No history entries in the requested range")]
+    EmptySession,
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    let path = std::path::PathBuf::from(&args.file);
+    let entries = read_entries(&path)?;
+
+    let entries = slice(
+        entries,
+        args.lines.as_deref(),
+        args.from.as_deref(),
+        args.to.as_deref(),
+    )?;
+
+    if entries.is_empty() {
+        return Err(SessionErrors::EmptySession.into());
+    }
+
+    let entries = if args.yes {
+        entries
+    } else {
+        // Leave room below the list for the preview pane
+        let list_height = (entries.len() as u16).min(20) + 5 + 8;
+        let muted = Theme::current().muted;
+        let mut select = SelectList::new(entries, "Session", display_entry)
+            .with_preview(PreviewPosition::Bottom, move |entry: &HistoryEntry| {
+                preview_entry(entry, muted)
+            })
+            .with_confirm("Export", "Cancel")
+            .with_toggleable();
+
+        let SelectResult::Many(indices) = select.prompt(list_height, 120)? else {
+            println!("Cancelled.");
+            return Ok(());
+        };
+        indices
+            .into_iter()
+            .map(|i| select.items()[i].clone())
+            .collect()
+    };
+
+    let rendered = match args.format {
+        Format::Bash => render_bash(&args.name, &args.file, &entries),
+        Format::Markdown => render_markdown(&args.name, &args.file, &entries),
+        Format::Just => render_just(&args.name, &entries),
+    };
+
+    match &args.output {
+        Some(output) => {
+            let output = std::path::Path::new(output);
+            let mut file = std::fs::File::create(output)?;
+            file.write_all(rendered.as_bytes())?;
+
+            #[cfg(unix)]
+            if matches!(args.format, Format::Bash) {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(output, std::fs::Permissions::from_mode(0o755))?;
+            }
+
+            println!(
+                "Exported {} commands to {}",
+                entries.len(),
+                output.display()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+/// The entries within the line range `lines`, or between the times `from`
+/// and `to`, or all of them when no range is given.
+fn slice(
+    entries: Vec<HistoryEntry>,
+    lines: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> crate::Result<Vec<HistoryEntry>> {
+    if let Some(range) = lines {
+        let (start, end) = parse_line_range(range)?;
+        return Ok(entries
+            .into_iter()
+            .filter(|e| e.line >= start && e.line <= end)
+            .collect());
+    }
+    if from.is_none() && to.is_none() {
+        return Ok(entries);
+    }
+    if entries.iter().all(|e| e.timestamp.is_none()) {
+        return Err(SessionErrors::MissingTimestamps.into());
+    }
+
+    let from = from.map(parse_time).transpose()?;
+    let to = to.map(parse_time).transpose()?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| {
+            e.timestamp.is_some_and(|ts| {
+                from.is_none_or(|from| ts >= from) && to.is_none_or(|to| ts <= to)
+            })
+        })
+        .collect())
+}
+
+fn display_entry(entry: &HistoryEntry) -> String {
+    let command = entry.command.replace('\n', " ⏎ ");
+    match entry.timestamp.and_then(format_time) {
+        Some(time) => format!("{time}  {command}"),
+        None => format!("{:>6}  {command}", entry.line),
+    }
+}
+
+/// The full command, newlines and all, followed by where it came from.
+fn preview_entry(entry: &HistoryEntry, muted: ratatui::style::Style) -> Text<'static> {
+    let mut lines: Vec<Line> = entry
+        .command
+        .lines()
+        .map(|line| Line::from(line.to_string()))
+        .collect();
+
+    let mut origin = format!("line {}", entry.line);
+    if let Some(time) = entry.timestamp.and_then(format_time) {
+        origin.push_str(&format!(", {time}"));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(origin, muted)));
+
+    Text::from(lines)
+}
+
+/// Parse a user supplied point in time into a unix timestamp, interpreting
+/// anything without an explicit offset in the local timezone.
+fn parse_time(input: &str) -> crate::Result<i64> {
+    use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+    let input = input.trim();
+    let invalid = || SessionErrors::InvalidTime(input.to_string());
+
+    if let Ok(ts) = input.parse::<i64>() {
+        return Ok(ts);
+    }
+
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(input) {
+        return Ok(time.timestamp());
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(input, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .or_else(|| {
+            ["%H:%M:%S", "%H:%M"]
+                .iter()
+                .find_map(|fmt| NaiveTime::parse_from_str(input, fmt).ok())
+                .map(|time| Local::now().date_naive().and_time(time))
+        })
+        .ok_or_else(invalid)?;
+
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.timestamp())
+        .ok_or_else(|| invalid().into())
+}
+
+fn parse_line_range(input: &str) -> crate::Result<(usize, usize)> {
+    let invalid = || SessionErrors::InvalidLineRange(input.to_string());
+    let (start, end) = input.split_once('-').ok_or_else(invalid)?;
+    let start = start.trim().parse::<usize>().map_err(|_| invalid())?;
+    let end = end.trim().parse::<usize>().map_err(|_| invalid())?;
+
+    if start > end {
+        return Err(invalid().into());
+    }
+
+    Ok((start, end))
+}
+
+fn render_bash(name: &str, source: &str, entries: &[HistoryEntry]) -> String {
+    let mut out = String::from("#!/usr/bin/env bash\n");
+    out.push_str(&format!("# {name}\n"));
+    out.push_str(&format!("# Exported from {source}{}\n", time_span(entries)));
+    out.push_str("set -euo pipefail\n\n");
+
+    for entry in entries {
+        out.push_str(&entry.command);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn render_markdown(name: &str, source: &str, entries: &[HistoryEntry]) -> String {
+    let mut out = format!("# {name}\n\n");
+    out.push_str(&format!(
+        "_Exported from `{source}`{}._\n",
+        time_span(entries)
+    ));
+
+    for (i, entry) in entries.iter().enumerate() {
+        out.push_str(&format!("\n## Step {}\n\n", i + 1));
+        if let Some(time) = entry.timestamp.and_then(format_time) {
+            out.push_str(&format!("Ran at {time}.\n\n"));
+        }
+        // A fence has to be longer than any run of backticks inside it
+        let fence = "`".repeat(longest_run(&entry.command, '`').max(2) + 1);
+        out.push_str(&format!("{fence}sh\n{}\n{fence}\n", entry.command));
+    }
+
+    out
+}
+
+fn render_just(name: &str, entries: &[HistoryEntry]) -> String {
+    let recipe = name
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    let recipe = recipe.trim_matches('-');
+    let recipe = if recipe.is_empty() { "session" } else { recipe };
+
+    // A shebang recipe runs as one script, so `cd` and variables carry over
+    // from one command to the next like they did in the shell
+    let mut out = format!("# {name}\n{recipe}:\n");
+    out.push_str("    #!/usr/bin/env bash\n    set -euo pipefail\n");
+    for entry in entries {
+        // Continuation lines of multi-line commands need the same indent to
+        // stay inside the recipe body, and `{{` starts an interpolation in
+        // just unless it's doubled
+        for line in entry.command.lines() {
+            out.push_str(&format!("    {}\n", line.replace("{{", "{{{{")));
+        }
+    }
+
+    out
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|other| other != c)
+        .map(str::len)
+        .max()
+        .unwrap_or(0)
+}
+
+fn time_span(entries: &[HistoryEntry]) -> String {
+    let first = entries
+        .iter()
+        .find_map(|e| e.timestamp.and_then(format_time));
+    let last = entries
+        .iter()
+        .rev()
+        .find_map(|e| e.timestamp.and_then(format_time));
+
+    match (first, last) {
+        (Some(first), Some(last)) => format!(" ({first} to {last})"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(contents: &str) -> Vec<HistoryEntry> {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), contents).unwrap();
+        read_entries(file.path()).unwrap()
+    }
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    fn entry(line: usize, timestamp: Option<i64>, command: &str) -> HistoryEntry {
+        HistoryEntry {
+            line,
+            timestamp,
+            command: command.to_string(),
+            raw: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_zsh_extended_history() {
+        let entries = read(": 100:0;ls\n: 200:3;echo a\\\nb\n: 300:0;pwd\n");
+
+        assert_eq!(commands(&entries), ["ls", "echo a\nb", "pwd"]);
+        assert_eq!(entries[1].timestamp, Some(200));
+        assert_eq!(entries[1].raw, ": 200:3;echo a\\\nb");
+        assert_eq!(entries[2].line, 4);
+    }
+
+    #[test]
+    fn reads_bash_timestamps_and_plain_lines() {
+        let entries = read("#100\nls\n#200\ncd /tmp\n");
+        assert_eq!(commands(&entries), ["ls", "cd /tmp"]);
+        assert_eq!(entries[1].timestamp, Some(200));
+        assert_eq!(entries[1].raw, "#200\ncd /tmp");
+        assert_eq!(entries[1].line, 4);
+
+        let entries = read("ls\n\ncd /tmp\n");
+        assert_eq!(commands(&entries), ["ls", "cd /tmp"]);
+        assert_eq!(entries[1].timestamp, None);
+        assert_eq!(entries[1].line, 3);
+    }
+
+    #[test]
+    fn keeps_bash_timestamps_across_blank_lines() {
+        let entries = read("#100\n\nls\n#200\ncd /tmp\n");
+
+        assert_eq!(commands(&entries), ["ls", "cd /tmp"]);
+        assert_eq!(entries[0].timestamp, Some(100));
+        assert_eq!(entries[0].lines(), 1..=3);
+        assert_eq!(entries[1].timestamp, Some(200));
+        assert_eq!(entries[1].lines(), 4..=5);
+    }
+
+    #[test]
+    fn slices_by_lines_or_time() {
+        let entries = vec![
+            entry(1, Some(100), "a"),
+            entry(2, Some(200), "b"),
+            entry(3, Some(300), "c"),
+        ];
+
+        let sliced = slice(entries.clone(), Some("2-3"), None, None).unwrap();
+        assert_eq!(commands(&sliced), ["b", "c"]);
+        let sliced = slice(entries.clone(), None, Some("150"), Some("300")).unwrap();
+        assert_eq!(commands(&sliced), ["b", "c"]);
+        let sliced = slice(entries.clone(), None, None, Some("200")).unwrap();
+        assert_eq!(commands(&sliced), ["a", "b"]);
+        assert_eq!(slice(entries, None, None, None).unwrap().len(), 3);
+
+        assert!(slice(vec![entry(1, None, "a")], None, Some("100"), None).is_err());
+        assert!(slice(Vec::new(), Some("3-2"), None, None).is_err());
+    }
+
+    #[test]
+    fn renders_bash_scripts() {
+        let rendered = render_bash("Deploy", "~/.zsh_history", &[entry(1, None, "make")]);
+
+        assert_eq!(
+            rendered,
+            "#!/usr/bin/env bash\n# Deploy\n# Exported from ~/.zsh_history\nset -euo pipefail\n\nmake\n"
+        );
+    }
+
+    #[test]
+    fn fences_markdown_longer_than_its_backticks() {
+        let entries = [entry(1, None, "ls"), entry(2, None, "echo '```'")];
+        let rendered = render_markdown("Deploy", "history", &entries);
+
+        assert!(rendered.starts_with("# Deploy\n\n_Exported from `history`._\n"));
+        assert!(rendered.contains("## Step 1\n\n```sh\nls\n```\n"));
+        assert!(rendered.contains("## Step 2\n\n````sh\necho '```'\n````\n"));
+    }
+
+    #[test]
+    fn escapes_interpolation_in_just_recipes() {
+        let entries = [entry(1, None, "docker ps --format '{{.Names}}'\necho done")];
+        let rendered = render_just("Deploy all!", &entries);
+
+        assert_eq!(
+            rendered,
+            "# Deploy all!\ndeploy-all:\n    #!/usr/bin/env bash\n    set -euo pipefail\n    docker ps --format '{{{{.Names}}'\n    echo done\n"
+        );
+    }
+
+    #[test]
+    fn runs_just_recipes_as_one_script() {
+        let entries = [entry(1, None, "cd build"), entry(2, None, "make")];
+        let rendered = render_just("Build", &entries);
+
+        assert_eq!(
+            rendered,
+            "# Build\nbuild:\n    #!/usr/bin/env bash\n    set -euo pipefail\n    cd build\n    make\n"
+        );
+    }
+}
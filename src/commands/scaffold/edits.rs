@@ -0,0 +1,108 @@
+//! Splicing changes into Rust source at the spans syn parsed, leaving the
+//! rest of the file byte for byte as it was
+use std::ops::Range;
+
+use proc_macro2::{LineColumn, Span};
+
+/// Changes to a source file at the positions syn parsed things from,
+/// applied together so earlier ones don't move the later ones.
+pub(super) struct Edits<'a> {
+    source: &'a str,
+    /// The byte offset every line starts at
+    lines: Vec<usize>,
+    changes: Vec<(Range<usize>, String)>,
+}
+
+impl<'a> Edits<'a> {
+    pub(super) fn new(source: &'a str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            lines,
+            changes: Vec::new(),
+        }
+    }
+
+    /// The byte offset of a position, whose column counts characters.
+    fn offset(&self, position: LineColumn) -> usize {
+        let start = self.lines[position.line - 1];
+        self.source[start..]
+            .char_indices()
+            .nth(position.column)
+            .map_or(self.source.len(), |(i, _)| start + i)
+    }
+
+    /// The whitespace the line at `position` starts with.
+    fn indent(&self, position: LineColumn) -> &'a str {
+        let start = self.lines[position.line - 1];
+        let line = &self.source[start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    pub(super) fn insert(&mut self, position: LineColumn, text: &str) {
+        let at = self.offset(position);
+        self.changes.push((at..at, text.to_string()));
+    }
+
+    pub(super) fn replace(&mut self, span: Span, text: &str) {
+        let range = self.offset(span.start())..self.offset(span.end());
+        self.changes.push((range, text.to_string()));
+    }
+
+    /// Add `lines` at the end of a braced block, just before its `close`
+    /// brace, indented like `last`, the block's last entry, or one level
+    /// deeper than the brace when it's empty.
+    pub(super) fn append(&mut self, close: Span, last: Option<Span>, lines: &[String]) {
+        let close = close.start();
+        let indent = match last {
+            Some(last) if last.start().line != close.line => self.indent(last.start()).to_string(),
+            _ => format!("{}    ", self.indent(close)),
+        };
+        let text: String = lines.iter().map(|l| format!("{indent}{l}\n")).collect();
+
+        let at = self.offset(close);
+        let line_start = self.lines[close.line - 1];
+        let before = self.source[line_start..at].trim_end();
+        if before.is_empty() {
+            self.changes.push((line_start..line_start, text));
+        } else {
+            // The brace shares its line, so it moves down to one of its own
+            let brace = format!("\n{text}{}", self.indent(close));
+            self.changes.push((line_start + before.len()..at, brace));
+        }
+    }
+
+    /// Remove what's between `start` and `end`, along with the lines it's
+    /// on when nothing else shares them.
+    pub(super) fn remove(&mut self, start: LineColumn, end: LineColumn) {
+        let (mut from, mut to) = (self.offset(start), self.offset(end));
+        let line_start = self.lines[start.line - 1];
+        let line_end = self.source[to..]
+            .find('\n')
+            .map_or(self.source.len(), |i| to + i + 1);
+        if self.source[line_start..from].trim().is_empty()
+            && self.source[to..line_end].trim().is_empty()
+        {
+            (from, to) = (line_start, line_end);
+        }
+        self.changes.push((from..to, String::new()));
+    }
+
+    /// The edited source, checked to still parse.
+    pub(super) fn apply(mut self) -> crate::Result<String> {
+        self.changes.sort_by_key(|(range, _)| range.start);
+        let mut output = String::with_capacity(self.source.len());
+        let mut copied = 0;
+        for (range, text) in &self.changes {
+            output.push_str(&self.source[copied..range.start]);
+            output.push_str(text);
+            copied = range.end;
+        }
+        output.push_str(&self.source[copied..]);
+
+        syn::parse_file(&output)?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,227 @@
+//! Removing and renaming commands, undoing or rewriting everything creating
+//! one did: its file or folder, its `mod` line and its place in main.rs
+use std::path::{Path, PathBuf};
+
+use ratatui::text::Line;
+use syn::spanned::Spanned;
+
+use super::edits::Edits;
+use super::template::pascal_case;
+use super::{
+    COMMANDS_FOLDER, MAIN_FILE, ScaffoldErrors, command_name, commands_module, main_file,
+    transaction, validate_name,
+};
+use crate::api::terminal::{Change, ConfirmPrompt};
+
+/// This command's own module, which it won't touch.
+const SCAFFOLD: &str = "scaffold";
+
+pub(super) fn remove_command(name: &str, yes: bool) -> crate::Result<()> {
+    let name = command_name(name);
+    if name == SCAFFOLD {
+        return Err(ScaffoldErrors::ScaffoldItself.into());
+    }
+    let dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?);
+    let path = command_path(&dir, &name)?;
+
+    let module = commands_module(&dir)?;
+    let module_source =
+        undeclare_module(&std::fs::read_to_string(dir.join(&module))?, &name, &module)?;
+    let main = main_file::unregister(&std::fs::read_to_string(dir.join(MAIN_FILE))?, &name)?;
+
+    let mut changes = vec![
+        Change::Removed(path.display().to_string()),
+        Change::Removed(format!("{}: pub(crate) mod {name};", module.display())),
+    ];
+    if main.is_some() {
+        changes.extend(registration(&name).map(Change::Removed));
+    }
+    let context = "  Removed files aren't kept anywhere, commit anything worth keeping first";
+    if !yes && !confirm(format!("Remove the {name} command?"), context, &changes)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    transaction::run(&dir, |transaction| {
+        transaction.remove(&path)?;
+        transaction.edit(&module, |_| Ok(module_source))?;
+        if let Some(main) = main {
+            transaction.edit(Path::new(MAIN_FILE), |_| Ok(main))?;
+        }
+        Ok(())
+    })?;
+
+    println!("Command {name} removed");
+    Ok(())
+}
+
+pub(super) fn rename_command(old: &str, new: &str, yes: bool) -> crate::Result<()> {
+    let (old, new) = (command_name(old), command_name(new));
+    if old == SCAFFOLD || new == SCAFFOLD {
+        return Err(ScaffoldErrors::ScaffoldItself.into());
+    }
+    validate_name(&new)?;
+    let dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?);
+    let from = command_path(&dir, &old)?;
+    if let Ok(existing) = command_path(&dir, &new) {
+        return Err(ScaffoldErrors::CommandFileExists(existing.display().to_string()).into());
+    }
+    let to = match from.extension() {
+        Some(_) => Path::new(COMMANDS_FOLDER).join(format!("{new}.rs")),
+        None => Path::new(COMMANDS_FOLDER).join(&new),
+    };
+
+    let module = commands_module(&dir)?;
+    let module_source = rename_module(
+        &std::fs::read_to_string(dir.join(&module))?,
+        &old,
+        &new,
+        &module,
+    )?;
+    let main = main_file::rename(&std::fs::read_to_string(dir.join(MAIN_FILE))?, &old, &new)?;
+
+    let mut changes = vec![
+        Change::Removed(from.display().to_string()),
+        Change::Added(to.display().to_string()),
+        Change::Removed(format!("{}: pub(crate) mod {old};", module.display())),
+        Change::Added(format!("{}: pub(crate) mod {new};", module.display())),
+    ];
+    if main.is_some() {
+        changes.extend(registration(&old).map(Change::Removed));
+        changes.extend(registration(&new).map(Change::Added));
+    }
+    let context = "  Code inside the command is left as it is, rename anything in there by hand";
+    if !yes && !confirm(format!("Rename {old} to {new}?"), context, &changes)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    transaction::run(&dir, |transaction| {
+        transaction.rename(&from, &to)?;
+        transaction.edit(&module, |_| Ok(module_source))?;
+        if let Some(main) = main {
+            transaction.edit(Path::new(MAIN_FILE), |_| Ok(main))?;
+        }
+        Ok(())
+    })?;
+
+    println!("Command {old} renamed to {new}");
+    Ok(())
+}
+
+fn confirm(header: String, context: &str, changes: &[Change]) -> crate::Result<bool> {
+    let context = vec![Line::from(context.to_string())];
+    let mut prompt = ConfirmPrompt::changes(header, context, changes);
+    prompt.prompt(prompt.height(20), 120)
+}
+
+/// The command's file, or its folder when it's split into several, relative
+/// to the project.
+fn command_path(dir: &Path, name: &str) -> crate::Result<PathBuf> {
+    let file = Path::new(COMMANDS_FOLDER).join(format!("{name}.rs"));
+    let folder = Path::new(COMMANDS_FOLDER).join(name);
+    [file, folder]
+        .into_iter()
+        .find(|path| dir.join(path).exists())
+        .ok_or_else(|| ScaffoldErrors::UnknownCommand(name.to_string()).into())
+}
+
+/// What registering `name` added to main.rs, as it's shown when confirming.
+fn registration(name: &str) -> [String; 2] {
+    let title = pascal_case(name);
+    [
+        format!("{MAIN_FILE}: {title}({name}::Arguments),"),
+        format!("{MAIN_FILE}: Commands::{title}(args) => {name}::run(args),"),
+    ]
+}
+
+/// Take the `mod` declaration for `name`, attributes and all, out of the
+/// commands module's `source`.
+fn undeclare_module(source: &str, name: &str, module: &Path) -> crate::Result<String> {
+    let file = syn::parse_file(source)?;
+    let span = declaration(&file, name, module)?.span();
+    let mut edits = Edits::new(source);
+    edits.remove(span.start(), span.end());
+    edits.apply()
+}
+
+/// Point the `mod` declaration for `old` in the commands module's `source`
+/// at `new`.
+fn rename_module(source: &str, old: &str, new: &str, module: &Path) -> crate::Result<String> {
+    let file = syn::parse_file(source)?;
+    if declaration(&file, new, module).is_ok() {
+        let module = module.display().to_string();
+        return Err(ScaffoldErrors::AlreadyDeclared(new.to_string(), module).into());
+    }
+    let mut edits = Edits::new(source);
+    edits.replace(declaration(&file, old, module)?.ident.span(), new);
+    edits.apply()
+}
+
+/// The `mod` item declaring `name` in the commands module.
+fn declaration<'a>(
+    file: &'a syn::File,
+    name: &str,
+    module: &Path,
+) -> Result<&'a syn::ItemMod, ScaffoldErrors> {
+    file.items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Mod(declaration) if declaration.ident == name => Some(declaration),
+            _ => None,
+        })
+        .ok_or_else(|| ScaffoldErrors::NotDeclared(name.to_string(), module.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = "pub(crate) mod example;\n#[cfg(debug_assertions)]\npub(crate) mod scaffold;\npub mod deploy;\n";
+
+    #[test]
+    fn edits_only_the_declaration() {
+        let module = Path::new("src/commands/mod.rs");
+
+        assert_eq!(
+            undeclare_module(MODULE, "deploy", module).unwrap(),
+            "pub(crate) mod example;\n#[cfg(debug_assertions)]\npub(crate) mod scaffold;\n"
+        );
+        assert_eq!(
+            rename_module(MODULE, "deploy", "ship", module).unwrap(),
+            "pub(crate) mod example;\n#[cfg(debug_assertions)]\npub(crate) mod scaffold;\npub mod ship;\n"
+        );
+    }
+
+    #[test]
+    fn removes_attributes_with_the_declaration() {
+        let module = Path::new("src/commands/mod.rs");
+
+        let source = undeclare_module(MODULE, "scaffold", module).unwrap();
+        assert_eq!(source, "pub(crate) mod example;\npub mod deploy;\n");
+
+        let file = syn::parse_file(&source).unwrap();
+        assert!(
+            declaration(&file, "deploy", module)
+                .unwrap()
+                .attrs
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn refuses_missing_and_taken_names() {
+        let module = Path::new("src/commands/mod.rs");
+
+        let error = undeclare_module(MODULE, "ship", module).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ship isn't declared in src/commands/mod.rs"
+        );
+        let error = rename_module(MODULE, "deploy", "example", module).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "example is already declared in src/commands/mod.rs"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Registering, unregistering and renaming commands in main.rs
+//!
+//! main.rs is parsed rather than searched line by line, so the `Commands`
+//! enum and the match on it are found wherever they are and however they're
+//! formatted. New commands go after every existing one, outside any of the
+//! `#[cfg(...)]` variants. Only the lines for the command change, spliced in
+//! where the parsed spans say, so the rest of the file keeps its formatting
+//! and comments byte for byte.
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{Arm, Expr, ExprMatch, File, Ident, Item, ItemEnum, Pat, Path};
+
+use super::edits::Edits;
+use super::template::pascal_case;
+
+/// The enum in main.rs every command is a variant of.
+const COMMANDS_ENUM: &str = "Commands";
+
+#[derive(thiserror::Error, Debug)]
+pub(super) enum MainFileErrors {
+    #[error("Couldn't find `enum {COMMANDS_ENUM}` in main.rs")]
+    MissingEnum,
+    #[error("Couldn't find the `match` on {COMMANDS_ENUM} in main.rs")]
+    MissingMatch,
+    #[error("{COMMANDS_ENUM}::{0} is already in main.rs")]
+    AlreadyRegistered(String),
+}
+
+/// Add `name` to the `Commands` enum in `source` and a match arm running it,
+/// returning the new main.rs.
+pub(super) fn register(source: &str, name: &str, description: &str) -> crate::Result<String> {
+    let file = syn::parse_file(source)?;
+    let title = pascal_case(name);
+    let mut edits = Edits::new(source);
+
+    let commands = commands_enum(&file)?;
+    if commands.variants.iter().any(|v| v.ident == title) {
+        return Err(MainFileErrors::AlreadyRegistered(title).into());
+    }
+    let mut variant = Vec::new();
+    if !description.is_empty() {
+        variant.push(format!("/// {}", description.replace('\n', " ")));
+    }
+    variant.push(format!("{title}({name}::Arguments),"));
+    match commands.variants.last() {
+        Some(last) if !commands.variants.trailing_punct() => edits.insert(last.span().end(), ","),
+        _ => {}
+    }
+    let last = commands.variants.last().map(|v| v.ident.span());
+    edits.append(commands.brace_token.span.close(), last, &variant);
+
+    let matches = command_matches(&file);
+    let command_match = matches.first().ok_or(MainFileErrors::MissingMatch)?;
+    let last = command_match.arms.last();
+    if let Some(last) = last
+        && last.comma.is_none()
+        && !matches!(*last.body, Expr::Block(_))
+    {
+        edits.insert(last.span().end(), ",");
+    }
+    let arm = format!("{COMMANDS_ENUM}::{title}(args) => {name}::run(args),");
+    let last = last.map(|arm| arm.pat.span());
+    edits.append(command_match.brace_token.span.close(), last, &[arm]);
+
+    edits.apply()
+}
+
+/// Take `name` out of the `Commands` enum in `source` along with its match
+/// arms, returning the new main.rs, or `None` when it was never registered.
+pub(super) fn unregister(source: &str, name: &str) -> crate::Result<Option<String>> {
+    let file = syn::parse_file(source)?;
+    let title = pascal_case(name);
+    let mut edits = Edits::new(source);
+
+    let commands = commands_enum(&file)?;
+    let Some(pair) = commands
+        .variants
+        .pairs()
+        .find(|pair| pair.value().ident == title)
+    else {
+        return Ok(None);
+    };
+    let end = match pair.punct() {
+        Some(comma) => comma.span.end(),
+        None => pair.value().span().end(),
+    };
+    edits.remove(pair.value().span().start(), end);
+
+    for command_match in command_matches(&file) {
+        for arm in command_match.arms.iter().filter(|arm| runs(arm, &title)) {
+            edits.remove(arm.span().start(), arm.span().end());
+        }
+    }
+    edits.apply().map(Some)
+}
+
+/// Rename the `Commands` variant for `old` in `source` after `new` and point
+/// it and its match arms at the `new` module, returning the new main.rs, or
+/// `None` when `old` was never registered.
+pub(super) fn rename(source: &str, old: &str, new: &str) -> crate::Result<Option<String>> {
+    let file = syn::parse_file(source)?;
+    let (old_title, new_title) = (pascal_case(old), pascal_case(new));
+    let mut edits = Edits::new(source);
+
+    let commands = commands_enum(&file)?;
+    if commands.variants.iter().any(|v| v.ident == new_title) {
+        return Err(MainFileErrors::AlreadyRegistered(new_title).into());
+    }
+    let Some(variant) = commands.variants.iter().find(|v| v.ident == old_title) else {
+        return Ok(None);
+    };
+    edits.replace(variant.ident.span(), &new_title);
+    let mut module = ModulePaths::new(old);
+    module.visit_variant(variant);
+
+    for command_match in command_matches(&file) {
+        for arm in command_match
+            .arms
+            .iter()
+            .filter(|arm| runs(arm, &old_title))
+        {
+            if let Some(path) = command_path(&arm.pat) {
+                edits.replace(path.segments[1].ident.span(), &new_title);
+            }
+            module.visit_arm(arm);
+        }
+    }
+    for ident in module.found {
+        edits.replace(ident.span(), new);
+    }
+    edits.apply().map(Some)
+}
+
+fn commands_enum(file: &File) -> crate::Result<&ItemEnum> {
+    file.items
+        .iter()
+        .find_map(|item| match item {
+            Item::Enum(item) if item.ident == COMMANDS_ENUM => Some(item),
+            _ => None,
+        })
+        .ok_or_else(|| MainFileErrors::MissingEnum.into())
+}
+
+/// Every `match` over `Commands` in `file`, in the order they appear.
+fn command_matches(file: &File) -> Vec<&ExprMatch> {
+    let mut matches = CommandMatches(Vec::new());
+    matches.visit_file(file);
+    matches.0
+}
+
+/// Collects the matches with an arm for a variant of `Commands`.
+struct CommandMatches<'ast>(Vec<&'ast ExprMatch>);
+
+impl<'ast> Visit<'ast> for CommandMatches<'ast> {
+    fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+        if node.arms.iter().any(|arm| command_path(&arm.pat).is_some()) {
+            self.0.push(node);
+        }
+        syn::visit::visit_expr_match(self, node);
+    }
+}
+
+/// Collects the module names in paths into one module, like `old` in
+/// `old::Arguments`.
+struct ModulePaths<'a, 'ast> {
+    module: &'a str,
+    found: Vec<&'ast Ident>,
+}
+
+impl<'a> ModulePaths<'a, '_> {
+    fn new(module: &'a str) -> Self {
+        Self {
+            module,
+            found: Vec::new(),
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ModulePaths<'_, 'ast> {
+    fn visit_path(&mut self, node: &'ast Path) {
+        if node.segments.len() > 1 && node.segments[0].ident == self.module {
+            self.found.push(&node.segments[0].ident);
+        }
+        syn::visit::visit_path(self, node);
+    }
+}
+
+/// Whether `arm` matches on the `Commands` variant called `title`.
+fn runs(arm: &Arm, title: &str) -> bool {
+    command_path(&arm.pat).is_some_and(|path| path.segments[1].ident == title)
+}
+
+/// The `Commands::Variant` path `pat` matches on, if it's one.
+fn command_path(pat: &Pat) -> Option<&Path> {
+    let path = match pat {
+        Pat::TupleStruct(pat) => &pat.path,
+        Pat::Path(pat) => &pat.path,
+        Pat::Struct(pat) => &pat.path,
+        _ => return None,
+    };
+    (path.segments.len() == 2 && path.segments[0].ident == COMMANDS_ENUM).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN: &str = r#"
+enum Commands {
+    #[cfg(debug_assertions)]
+    Scaffold(scaffold::Arguments),
+    /// Perform operations on your command history
+    History(history::Arguments),
+}
+
+fn main() -> crate::Result<()> {
+    if let Some(cmds) = &cli.command {
+        match cmds {
+            #[cfg(debug_assertions)]
+            Commands::Scaffold(args) => scaffold::run(args),
+            Commands::History(args) => history::run(args),
+        }?;
+    };
+    Ok(())
+}
+"#;
+
+    #[test]
+    fn registers_after_every_command_without_a_cfg() {
+        let main = register(MAIN, "deploy_all", "Ship it").unwrap();
+
+        assert!(main.contains(
+            "    History(history::Arguments),\n    /// Ship it\n    DeployAll(deploy_all::Arguments),\n}"
+        ));
+        assert!(main.contains(
+            "Commands::History(args) => history::run(args),\n            Commands::DeployAll(args) => deploy_all::run(args),\n        }"
+        ));
+        assert_eq!(main.matches("#[cfg(debug_assertions)]").count(), 2);
+    }
+
+    #[test]
+    fn registering_only_adds_lines_to_main_rs() {
+        let source = include_str!("../../main.rs");
+        let main = register(source, "deploy", "Ship it").unwrap();
+
+        let added = ["    /// Ship it", "    Deploy(deploy::Arguments),"];
+        let arm = "Commands::Deploy(args) => deploy::run(args),";
+        let kept: Vec<&str> = main
+            .lines()
+            .filter(|line| !added.contains(line) && line.trim() != arm)
+            .collect();
+        assert_eq!(kept, source.lines().collect::<Vec<_>>());
+        assert_eq!(main.lines().count(), source.lines().count() + 3);
+
+        assert_eq!(unregister(&main, "deploy").unwrap().unwrap(), source);
+    }
+
+    #[test]
+    fn keeps_comments_and_formatting() {
+        let source = "// keep me\nenum Commands { A(a::Arguments) }\n\nfn main() {\n    match x { Commands::A(args) => { a::run(args) } };\n}\n";
+        let main = register(source, "b", "").unwrap();
+
+        assert_eq!(
+            main,
+            "// keep me\nenum Commands { A(a::Arguments),\n    B(b::Arguments),\n}\n\nfn main() {\n    match x { Commands::A(args) => { a::run(args) }\n        Commands::B(args) => b::run(args),\n    };\n}\n"
+        );
+    }
+
+    #[test]
+    fn refuses_to_register_twice() {
+        let main = register(MAIN, "deploy", "").unwrap();
+        assert!(!main.contains("///  "));
+
+        let error = register(&main, "deploy", "").unwrap_err();
+        assert_eq!(error.to_string(), "Commands::Deploy is already in main.rs");
+    }
+
+    #[test]
+    fn unregisters_the_variant_and_its_arm() {
+        let main = register(MAIN, "deploy", "Ship it").unwrap();
+        let main = unregister(&main, "deploy").unwrap().unwrap();
+
+        assert_eq!(main, MAIN);
+        let main = unregister(&main, "scaffold").unwrap().unwrap();
+        assert!(!main.contains("Scaffold"));
+        assert_eq!(main.matches("#[cfg(debug_assertions)]").count(), 0);
+        assert_eq!(unregister(&main, "deploy").unwrap(), None);
+    }
+
+    #[test]
+    fn renames_the_variant_arm_and_module() {
+        let main = rename(MAIN, "history", "past").unwrap().unwrap();
+
+        assert_eq!(
+            main,
+            MAIN.replace("History(", "Past(")
+                .replace("history::", "past::")
+        );
+        assert_eq!(rename(MAIN, "deploy", "past").unwrap(), None);
+
+        let error = rename(MAIN, "history", "scaffold").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Commands::Scaffold is already in main.rs"
+        );
+    }
+}
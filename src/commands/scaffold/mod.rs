@@ -0,0 +1,411 @@
+//! Meta scaffolding file for creating, removing and renaming commands
+//! @note This command will not show in release builds and is only meant to help
+//!       create new commands in development.
+//!
+//!       Unless you want to change the way your commands are created, you can
+//!       ignore this file.
+mod edits;
+mod existing;
+mod main_file;
+mod template;
+mod transaction;
+
+use std::path::{Path, PathBuf};
+
+use ratatui::text::Line;
+
+use crate::api::terminal::{Change, ConfirmPrompt, Form, Progress, TextInput};
+use template::{Context, DEFAULT_TEMPLATE, Template, pascal_case};
+
+const TEMPLATES_FOLDER: &str = ".meta/templates";
+const COMMANDS_FOLDER: &str = "src/commands";
+const MAIN_FILE: &str = "src/main.rs";
+
+#[derive(clap::Args)]
+#[command(arg_required_else_help = true)]
+pub(crate) struct Arguments {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+}
+
+const COMMAND_ABOUT: &str = "
+Meta scaffolding command for creating new commands
+
+The command will create a new command file in the commands directory, declare 
+it in the commands module, whether that's commands.rs or commands/mod.rs, and 
+make modifications to main.rs for you to start working with the command. If 
+any step fails, everything done before it is undone.
+
+The files are filled in from a template in .meta/templates, where {{name}}, 
+{{Name}}, {{NAME}} and {{description}} are replaced and {{#if ...}} sections 
+kept or dropped. Templates can be a single .rs file or a folder of files.";
+
+#[derive(clap::Subcommand)]
+pub(crate) enum Commands {
+    #[clap(about = "Meta scaffolding command for creating new commands")]
+    #[clap(long_about = COMMAND_ABOUT)]
+    Command {
+        /// Name of the new command, asked for when left out
+        name: Option<String>,
+        #[clap(long, short)]
+        /// Template in .meta/templates to create the command from
+        template: Option<String>,
+        #[clap(long, short)]
+        /// What the command does, shown in its help
+        description: Option<String>,
+        #[clap(long, short)]
+        /// Make the changes without confirming them first
+        yes: bool,
+    },
+    #[clap(about = "Remove a command and undo its changes to the commands module and main.rs")]
+    Remove {
+        /// Name of the command to remove
+        name: String,
+        #[clap(long, short)]
+        /// Make the changes without confirming them first
+        yes: bool,
+    },
+    #[clap(about = "Rename a command along with its module and its variant in main.rs")]
+    Rename {
+        /// Current name of the command
+        old: String,
+        /// Name to rename it to
+        new: String,
+        #[clap(long, short)]
+        /// Make the changes without confirming them first
+        yes: bool,
+    },
+}
+
+/// Everything asked for to create a command.
+struct NewCommand {
+    name: String,
+    description: String,
+    template: String,
+    register: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ScaffoldErrors {
+    #[error("{0} already exists")]
+    CommandFileExists(String),
+    #[error("Couldn't find the commands module, expected src/commands.rs or src/commands/mod.rs")]
+    MissingCommandsModule,
+    #[error("Both src/commands.rs and src/commands/mod.rs exist, remove one of them")]
+    AmbiguousCommandsModule,
+    #[error("{0} is already declared in {1}")]
+    AlreadyDeclared(String, String),
+    #[error("{0} isn't declared in {1}")]
+    NotDeclared(String, String),
+    #[error("There's no {0} command in src/commands")]
+    UnknownCommand(String),
+    #[error(
+        "`{0}` isn't a valid command name, it has to start with a letter and can't be a Rust keyword"
+    )]
+    InvalidName(String),
+    #[error("The scaffold command can't remove or rename itself")]
+    ScaffoldItself,
+}
+
+pub(crate) fn run(args: &Arguments) -> crate::Result<()> {
+    if let Some(command) = &args.command {
+        match command {
+            Commands::Command {
+                name: Some(name),
+                template,
+                description,
+                yes,
+            } => {
+                let name = command_name(name);
+                validate_name(&name)?;
+                let command = NewCommand {
+                    name,
+                    description: description.clone().unwrap_or_default(),
+                    template: template.as_deref().unwrap_or(DEFAULT_TEMPLATE).to_string(),
+                    register: true,
+                };
+                scaffold_command(&command, *yes)
+            }
+            Commands::Command {
+                name: None,
+                template,
+                description,
+                yes,
+            } => match ask_command(template.as_deref(), description.as_deref())? {
+                Some(command) => scaffold_command(&command, *yes),
+                None => {
+                    println!("Cancelled.");
+                    Ok(())
+                }
+            },
+            Commands::Remove { name, yes } => existing::remove_command(name, *yes),
+            Commands::Rename { old, new, yes } => existing::rename_command(old, new, *yes),
+        }?;
+    };
+
+    Ok(())
+}
+
+/// Ask for the command name, description and template and whether to
+/// register it in main.rs, skipping what was already given.
+fn ask_command(
+    template: Option<&str>,
+    description: Option<&str>,
+) -> crate::Result<Option<NewCommand>> {
+    let dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?);
+    let commands = dir.join(COMMANDS_FOLDER);
+
+    let name = TextInput::new("Name")
+        .with_placeholder("e.g. deploy")
+        .with_validation(move |value| {
+            let name = command_name(value);
+            validate_name(&name).map_err(|e| e.to_string())?;
+            if commands.join(format!("{name}.rs")).exists() || commands.join(&name).exists() {
+                return Err(format!("{name} already exists"));
+            }
+            Ok(())
+        });
+    let mut form = Form::new("New command").input("name", name);
+    if description.is_none() {
+        let input = TextInput::new("Description").with_placeholder("optional");
+        form = form.input("description", input);
+    }
+    if template.is_none() {
+        let templates = template::available(&dir)?;
+        if templates.is_empty() {
+            return Err(template::TemplateErrors::NoneFound.into());
+        }
+        form = form.select("template", "Template", templates);
+    }
+    let mut form = form.confirm("register", "Add to main.rs", true);
+
+    if !form.prompt(60)? {
+        return Ok(None);
+    }
+
+    let answer = |key, given: Option<&str>| {
+        given
+            .or_else(|| form.value(key))
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    Ok(Some(NewCommand {
+        name: command_name(form.value("name").unwrap_or_default()),
+        description: answer("description", description),
+        template: answer("template", template),
+        register: form.flag("register").unwrap_or(true),
+    }))
+}
+
+/// The module name a command is created under.
+fn command_name(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .trim_end_matches(".rs")
+        .to_owned()
+}
+
+/// Check `name` makes a usable module name, once it's been through
+/// [`command_name`].
+fn validate_name(name: &str) -> Result<(), ScaffoldErrors> {
+    // syn won't take a keyword as an identifier
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || syn::parse_str::<syn::Ident>(name).is_err()
+    {
+        return Err(ScaffoldErrors::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+fn scaffold_command(command: &NewCommand, yes: bool) -> crate::Result<()> {
+    let NewCommand {
+        name,
+        description,
+        register,
+        ..
+    } = command;
+    let dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR")?);
+
+    let context = Context::command(name, description, *register);
+    let files = Template::load(&dir, &command.template)?.render(&context)?;
+    if let Some((path, _)) = files.iter().find(|(path, _)| dir.join(path).exists()) {
+        return Err(ScaffoldErrors::CommandFileExists(path.display().to_string()).into());
+    }
+    let module = commands_module(&dir)?;
+
+    if !yes && !confirm_changes(command, &files, &module)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let steps = files.len() as u64 + 1 + u64::from(*register);
+    // Ctrl+C rolls back what's been done so far rather than leaving it
+    Progress::bar(format!("Creating {name} command"), steps)
+        .with_summary(format!("Command {name} created successfully!"))
+        .cancellable()
+        .show({
+            let name = name.clone();
+            let description = description.clone();
+            let register = *register;
+            move |progress| {
+                transaction::run(&dir, |transaction| {
+                    for (path, contents) in &files {
+                        progress.check_cancelled()?;
+                        transaction.create(path, contents)?;
+                        progress.message(format!("Created {}", path.display()));
+                        progress.advance(1);
+                    }
+
+                    progress.check_cancelled()?;
+                    transaction.edit(&module, |source| declare_module(source, &name, &module))?;
+                    progress.message(format!("Added {name} to {}", module.display()));
+                    progress.advance(1);
+
+                    progress.check_cancelled()?;
+                    if register {
+                        transaction.edit(Path::new(MAIN_FILE), |source| {
+                            main_file::register(source, &name, &description)
+                        })?;
+                        progress.message(format!("Added {name} to {MAIN_FILE}"));
+                        progress.advance(1);
+                    }
+                    Ok(())
+                })
+            }
+        })?;
+
+    if !register {
+        println!("Add {name} to main.rs when you're ready!");
+    }
+    Ok(())
+}
+
+/// List the files and lines the command will add and ask to go ahead.
+fn confirm_changes(
+    command: &NewCommand,
+    files: &[(PathBuf, String)],
+    module: &Path,
+) -> crate::Result<bool> {
+    let name = &command.name;
+    let title = pascal_case(name);
+    let mut changes: Vec<_> = files
+        .iter()
+        .map(|(path, _)| Change::Added(path.display().to_string()))
+        .collect();
+    changes.push(Change::Added(format!(
+        "{}: pub(crate) mod {name};",
+        module.display()
+    )));
+    if command.register {
+        changes.push(Change::Added(format!(
+            "{MAIN_FILE}: {title}({name}::Arguments),"
+        )));
+        changes.push(Change::Added(format!(
+            "{MAIN_FILE}: Commands::{title}(args) => {name}::run(args),"
+        )));
+    }
+
+    let context = vec![Line::from(format!(
+        "  From the {} template",
+        command.template
+    ))];
+    let mut prompt =
+        ConfirmPrompt::changes(format!("Create the {name} command?"), context, &changes);
+    prompt.prompt(prompt.height(20), 120)
+}
+
+/// The file the command modules are declared in, relative to the project.
+/// Either `src/commands.rs` or `src/commands/mod.rs`, as long as it's only
+/// one of them.
+fn commands_module(dir: &Path) -> crate::Result<PathBuf> {
+    let sibling = PathBuf::from(format!("{COMMANDS_FOLDER}.rs"));
+    let nested = Path::new(COMMANDS_FOLDER).join("mod.rs");
+
+    match (dir.join(&sibling).is_file(), dir.join(&nested).is_file()) {
+        (true, true) => Err(ScaffoldErrors::AmbiguousCommandsModule.into()),
+        (true, false) => Ok(sibling),
+        (false, true) => Ok(nested),
+        (false, false) => Err(ScaffoldErrors::MissingCommandsModule.into()),
+    }
+}
+
+/// Append a `mod` line for `name` to the commands module's `source`.
+fn declare_module(source: &str, name: &str, module: &Path) -> crate::Result<String> {
+    if source.lines().any(|line| declares(line, name)) {
+        let module = module.display().to_string();
+        return Err(ScaffoldErrors::AlreadyDeclared(name.to_string(), module).into());
+    }
+
+    let mut source = source.to_string();
+    if !source.is_empty() && !source.ends_with('\n') {
+        source.push('\n');
+    }
+    source.push_str(&format!("pub(crate) mod {name};\n"));
+    Ok(source)
+}
+
+/// Whether `line` is the `mod` declaration for `name`, public or not.
+fn declares(line: &str, name: &str) -> bool {
+    let line = line.trim();
+    let line = ["pub(crate) ", "pub "]
+        .iter()
+        .find_map(|visibility| line.strip_prefix(visibility))
+        .unwrap_or(line);
+    line.strip_prefix("mod ")
+        .is_some_and(|rest| rest.trim_end_matches(';').trim() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_either_module_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = dir.path().join(COMMANDS_FOLDER);
+        std::fs::create_dir_all(&commands).unwrap();
+        assert!(commands_module(dir.path()).is_err());
+
+        std::fs::write(commands.join("mod.rs"), "").unwrap();
+        assert_eq!(
+            commands_module(dir.path()).unwrap(),
+            Path::new("src/commands/mod.rs")
+        );
+
+        std::fs::write(dir.path().join("src/commands.rs"), "").unwrap();
+        assert!(commands_module(dir.path()).is_err());
+
+        std::fs::remove_file(commands.join("mod.rs")).unwrap();
+        assert_eq!(
+            commands_module(dir.path()).unwrap(),
+            Path::new("src/commands.rs")
+        );
+    }
+
+    #[test]
+    fn rejects_names_that_arent_modules() {
+        for name in ["deploy", "deploy_2", "types"] {
+            assert!(validate_name(&command_name(name)).is_ok(), "{name}");
+        }
+        for name in ["", "  ", "1deploy", "_deploy", "type", "Match", "self"] {
+            assert!(validate_name(&command_name(name)).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn declares_each_module_once() {
+        let module = Path::new("src/commands/mod.rs");
+        let source = declare_module("pub(crate) mod a;", "deploy", module).unwrap();
+        assert_eq!(source, "pub(crate) mod a;\npub(crate) mod deploy;\n");
+
+        let error = declare_module(&source, "deploy", module).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "deploy is already declared in src/commands/mod.rs"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Templates in `.meta/templates` and the placeholders they're filled in with
+//!
+//! A template is either a single `<name>.rs` file, which becomes
+//! `src/commands/{{name}}.rs`, or a `<name>/` folder whose files are copied
+//! into `src/commands` keeping their relative paths. Paths can use the same
+//! placeholders as the contents, e.g. `module/{{name}}/mod.rs`.
+//!
+//! Inside a template:
+//! - `{{name}}`, `{{Name}}` and `{{NAME}}` are the command's module, type and
+//!   constant names, and `{{description}}` is what it was described as
+//! - `{{#if key}} ... {{else}} ... {{/if}}` keeps a section when `key` is a
+//!   flag that's set or a value that isn't empty, `{{#unless key}}` when not
+//! - section tags on a line of their own take the whole line with them
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{COMMANDS_FOLDER, TEMPLATES_FOLDER};
+
+/// The template used when none is asked for.
+pub(super) const DEFAULT_TEMPLATE: &str = "command";
+
+#[derive(thiserror::Error, Debug)]
+pub(super) enum TemplateErrors {
+    #[error("There's no `{0}` template in .meta/templates, try one of: {1}")]
+    Unknown(String, String),
+    #[error("Invalid template {0}")]
+    Invalid(String, #[source] SyntaxErrors),
+    #[error("No templates found in .meta/templates")]
+    NoneFound,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub(super) enum SyntaxErrors {
+    #[error("Unknown placeholder `{{{{{0}}}}}`")]
+    UnknownPlaceholder(String),
+    #[error("`{{{{` is never closed with `}}}}`")]
+    UnclosedTag,
+    #[error("`{{{{{0}}}}}` doesn't match an open section")]
+    UnexpectedTag(String),
+    #[error("`{{{{#{0}}}}}` is never closed")]
+    UnclosedSection(String),
+}
+
+enum Value {
+    Text(String),
+    Flag(bool),
+}
+
+/// The values a template is filled in with.
+pub(super) struct Context {
+    values: HashMap<&'static str, Value>,
+}
+
+impl Context {
+    /// Everything a command template can refer to.
+    pub(super) fn command(name: &str, description: &str, register: bool) -> Self {
+        let values = HashMap::from([
+            ("name", Value::Text(name.to_string())),
+            ("Name", Value::Text(pascal_case(name))),
+            ("NAME", Value::Text(name.to_uppercase())),
+            ("description", Value::Text(description.to_string())),
+            ("register", Value::Flag(register)),
+        ]);
+        Self { values }
+    }
+
+    fn text(&self, key: &str) -> Result<&str, SyntaxErrors> {
+        match self.values.get(key) {
+            Some(Value::Text(text)) => Ok(text),
+            _ => Err(SyntaxErrors::UnknownPlaceholder(key.to_string())),
+        }
+    }
+
+    fn is_set(&self, key: &str) -> Result<bool, SyntaxErrors> {
+        match self.values.get(key) {
+            Some(Value::Text(text)) => Ok(!text.is_empty()),
+            Some(Value::Flag(flag)) => Ok(*flag),
+            None => Err(SyntaxErrors::UnknownPlaceholder(key.to_string())),
+        }
+    }
+}
+
+/// The type name a command is known by, e.g. `DeployAll` for `deploy_all`.
+pub(super) fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// A template's files, by where they're read from and the path under
+/// `src/commands` they're written to before placeholders are filled in.
+pub(super) struct Template {
+    files: Vec<(PathBuf, String)>,
+}
+
+/// The names of every template in `.meta/templates`, the default first.
+pub(super) fn available(dir: &Path) -> crate::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(dir.join(TEMPLATES_FOLDER))? {
+        let path = entry?.path();
+        let name = if path.is_dir() {
+            path.file_name()
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            path.file_stem()
+        } else {
+            None
+        };
+        if let Some(name) = name {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+
+    names.sort_by_key(|name| (name != DEFAULT_TEMPLATE, name.clone()));
+    names.dedup();
+    Ok(names)
+}
+
+impl Template {
+    /// Find the template called `name`.
+    pub(super) fn load(dir: &Path, name: &str) -> crate::Result<Self> {
+        let templates = dir.join(TEMPLATES_FOLDER);
+
+        let file = templates.join(format!("{name}.rs"));
+        if file.is_file() {
+            return Ok(Self {
+                files: vec![(file, "{{name}}.rs".to_string())],
+            });
+        }
+
+        let folder = templates.join(name);
+        if !folder.is_dir() {
+            let names = available(dir).unwrap_or_default().join(", ");
+            return Err(TemplateErrors::Unknown(name.to_string(), names).into());
+        }
+
+        let mut files = Vec::new();
+        collect_files(&folder, &folder, &mut files)?;
+        files.sort();
+        Ok(Self { files })
+    }
+
+    /// Fill in every file, returning the paths relative to the project and
+    /// their contents.
+    pub(super) fn render(&self, context: &Context) -> crate::Result<Vec<(PathBuf, String)>> {
+        self.files
+            .iter()
+            .map(|(source, target)| {
+                let invalid = |e| TemplateErrors::Invalid(source.display().to_string(), e);
+                let contents = std::fs::read_to_string(source)?;
+                let path = render(target, context).map_err(invalid)?;
+                let contents = render(&contents, context).map_err(invalid)?;
+                Ok((Path::new(COMMANDS_FOLDER).join(path), contents))
+            })
+            .collect()
+    }
+}
+
+fn collect_files(
+    root: &Path,
+    folder: &Path,
+    files: &mut Vec<(PathBuf, String)>,
+) -> crate::Result<()> {
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        let relative = path.strip_prefix(root)?.to_string_lossy().into_owned();
+        files.push((path, relative));
+    }
+    Ok(())
+}
+
+/// An `{{#if}}` or `{{#unless}}` that hasn't been closed yet.
+struct Section {
+    kind: String,
+    keep: bool,
+    seen_else: bool,
+}
+
+/// Fill the placeholders and sections in `source` from `context`.
+pub(super) fn render(source: &str, context: &Context) -> Result<String, SyntaxErrors> {
+    let mut output = String::new();
+    let mut sections: Vec<Section> = Vec::new();
+    let mut rest = source;
+    let mut line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or(SyntaxErrors::UnclosedTag)? + start;
+        let tag = rest[start + 2..end].trim();
+        let mut text = &rest[..start];
+        let mut after = &rest[end + 2..];
+
+        let is_section = tag.starts_with(['#', '/']) || tag == "else";
+        let takes_line = is_section && standalone(text, after, line_start);
+        if takes_line {
+            text = text.trim_end_matches([' ', '\t']);
+            after = match after.find('\n') {
+                Some(newline) => &after[newline + 1..],
+                None => "",
+            };
+        }
+
+        let keep = sections.iter().all(|s| s.keep);
+        if keep {
+            output.push_str(text);
+        }
+
+        if let Some(open) = tag.strip_prefix('#') {
+            let (kind, key) = open.split_once(' ').unwrap_or((open, ""));
+            let set = context.is_set(key.trim())?;
+            let keep = match kind {
+                "if" => set,
+                "unless" => !set,
+                _ => return Err(SyntaxErrors::UnexpectedTag(tag.to_string())),
+            };
+            sections.push(Section {
+                kind: kind.to_string(),
+                keep,
+                seen_else: false,
+            });
+        } else if let Some(close) = tag.strip_prefix('/') {
+            match sections.pop() {
+                Some(section) if section.kind == close => {}
+                _ => return Err(SyntaxErrors::UnexpectedTag(tag.to_string())),
+            }
+        } else if tag == "else" {
+            match sections.last_mut() {
+                Some(section) if !section.seen_else => {
+                    section.keep = !section.keep;
+                    section.seen_else = true;
+                }
+                _ => return Err(SyntaxErrors::UnexpectedTag(tag.to_string())),
+            }
+        } else {
+            let value = context.text(tag)?;
+            if keep {
+                output.push_str(value);
+            }
+        }
+
+        line_start = takes_line;
+        rest = after;
+    }
+
+    if let Some(section) = sections.pop() {
+        return Err(SyntaxErrors::UnclosedSection(section.kind));
+    }
+    if sections.iter().all(|s| s.keep) {
+        output.push_str(rest);
+    }
+    Ok(output)
+}
+
+/// Whether a tag between `before` and `after` is alone on its line.
+fn standalone(before: &str, after: &str, line_start: bool) -> bool {
+    let blank = |s: &str| s.chars().all(|c| c == ' ' || c == '\t' || c == '\r');
+    let line_before = match before.rfind('\n') {
+        Some(newline) => &before[newline + 1..],
+        None if line_start => before,
+        None => return false,
+    };
+    let line_after = after.split('\n').next().unwrap_or("");
+    blank(line_before) && blank(line_after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        Context::command("deploy_all", "Ship it", false)
+    }
+
+    #[test]
+    fn fills_in_name_cases_and_description() {
+        let rendered = render(
+            "mod {{name}}; struct {{ Name }}; const {{NAME}}: &str = \"{{description}}\";",
+            &context(),
+        );
+
+        assert_eq!(
+            rendered.unwrap(),
+            "mod deploy_all; struct DeployAll; const DEPLOY_ALL: &str = \"Ship it\";"
+        );
+    }
+
+    #[test]
+    fn keeps_sections_by_flag_and_value() {
+        let source = "\
+{{#if description}}
+//! {{description}}
+{{/if}}
+{{#if register}}
+registered
+{{else}}
+    #![allow(dead_code)]
+{{/if}}
+fn a() {}{{#unless description}} // none{{/unless}}
+";
+
+        assert_eq!(
+            render(source, &context()).unwrap(),
+            "//! Ship it\n    #![allow(dead_code)]\nfn a() {}\n"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_unbalanced_tags() {
+        let context = context();
+
+        assert_eq!(
+            render("{{nme}}", &context),
+            Err(SyntaxErrors::UnknownPlaceholder("nme".to_string()))
+        );
+        assert_eq!(
+            render("{{#if register}}", &context),
+            Err(SyntaxErrors::UnclosedSection("if".to_string()))
+        );
+        assert_eq!(
+            render("{{#if register}}{{/unless}}", &context),
+            Err(SyntaxErrors::UnexpectedTag("/unless".to_string()))
+        );
+        assert_eq!(render("{{name", &context), Err(SyntaxErrors::UnclosedTag));
+    }
+
+    #[test]
+    fn pascal_cases_snake_names() {
+        assert_eq!(pascal_case("deploy"), "Deploy");
+        assert_eq!(pascal_case("deploy_all2"), "DeployAll2");
+    }
+}
@@ -0,0 +1,302 @@
+//! Changes to the project that are undone together when one of them fails
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(thiserror::Error, Debug)]
+pub(super) enum TransactionErrors {
+    #[error("{0} already exists")]
+    Exists(String),
+    #[error("{0} doesn't exist")]
+    Missing(String),
+    #[error("Couldn't undo the changes to {0}, check them by hand")]
+    RollbackFailed(String, #[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Something done that rolling back has to undo.
+enum Undo {
+    Created(PathBuf),
+    CreatedFolder(PathBuf),
+    Edited(PathBuf, String),
+    Removed(PathBuf, Vec<u8>, std::fs::Permissions),
+    RemovedFolder(PathBuf),
+    RemovedLink(PathBuf, PathBuf),
+    Renamed(PathBuf, PathBuf),
+}
+
+/// Files created, edited, removed and renamed under the project, remembered
+/// until the whole change goes through.
+pub(super) struct Transaction {
+    dir: PathBuf,
+    undo: Vec<Undo>,
+}
+
+/// Run `changes` against the project in `dir`, putting back every file they
+/// touched if they fail.
+pub(super) fn run<T>(
+    dir: &Path,
+    changes: impl FnOnce(&mut Transaction) -> crate::Result<T>,
+) -> crate::Result<T> {
+    let mut transaction = Transaction {
+        dir: dir.to_path_buf(),
+        undo: Vec::new(),
+    };
+
+    match changes(&mut transaction) {
+        Ok(value) => Ok(value),
+        Err(error) => match transaction.rollback() {
+            Ok(()) => Err(error),
+            Err(path) => Err(TransactionErrors::RollbackFailed(
+                path.display().to_string(),
+                error.into(),
+            )
+            .into()),
+        },
+    }
+}
+
+impl Transaction {
+    /// Create the file at `path`, relative to the project, along with any
+    /// folders it needs.
+    pub(super) fn create(&mut self, path: &Path, contents: &str) -> crate::Result<()> {
+        let target = self.dir.join(path);
+        if target.exists() {
+            return Err(TransactionErrors::Exists(path.display().to_string()).into());
+        }
+
+        let mut missing = Vec::new();
+        let mut parent = target.parent();
+        while let Some(folder) = parent.filter(|folder| !folder.exists()) {
+            missing.push(folder.to_path_buf());
+            parent = folder.parent();
+        }
+        for folder in missing.into_iter().rev() {
+            std::fs::create_dir(&folder)?;
+            self.undo.push(Undo::CreatedFolder(folder));
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)?;
+        // A failed write still leaves the file behind to be removed
+        self.undo.push(Undo::Created(target));
+        file.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+
+    /// Replace the contents of `path`, relative to the project, with what
+    /// `edit` makes of them.
+    pub(super) fn edit(
+        &mut self,
+        path: &Path,
+        edit: impl FnOnce(&str) -> crate::Result<String>,
+    ) -> crate::Result<()> {
+        let target = self.dir.join(path);
+        let original = std::fs::read_to_string(&target)?;
+        let edited = edit(&original)?;
+
+        write(&target, &edited)?;
+        self.undo.push(Undo::Edited(target, original));
+        Ok(())
+    }
+
+    /// Delete the file or whole folder at `path`, relative to the project.
+    pub(super) fn remove(&mut self, path: &Path) -> crate::Result<()> {
+        let target = self.dir.join(path);
+        if target.symlink_metadata().is_err() {
+            return Err(TransactionErrors::Missing(path.display().to_string()).into());
+        }
+        self.remove_all(target)
+    }
+
+    fn remove_all(&mut self, target: PathBuf) -> crate::Result<()> {
+        // Links are removed themselves, never what they point at
+        let kind = std::fs::symlink_metadata(&target)?.file_type();
+        if kind.is_symlink() {
+            let link = std::fs::read_link(&target)?;
+            std::fs::remove_file(&target)?;
+            self.undo.push(Undo::RemovedLink(target, link));
+            return Ok(());
+        }
+        if kind.is_dir() {
+            for entry in std::fs::read_dir(&target)? {
+                self.remove_all(entry?.path())?;
+            }
+            std::fs::remove_dir(&target)?;
+            self.undo.push(Undo::RemovedFolder(target));
+            return Ok(());
+        }
+
+        let contents = std::fs::read(&target)?;
+        let permissions = std::fs::metadata(&target)?.permissions();
+        std::fs::remove_file(&target)?;
+        self.undo.push(Undo::Removed(target, contents, permissions));
+        Ok(())
+    }
+
+    /// Move the file or folder at `from` to `to`, both relative to the
+    /// project.
+    pub(super) fn rename(&mut self, from: &Path, to: &Path) -> crate::Result<()> {
+        let (source, target) = (self.dir.join(from), self.dir.join(to));
+        if !source.exists() {
+            return Err(TransactionErrors::Missing(from.display().to_string()).into());
+        }
+        if target.exists() {
+            return Err(TransactionErrors::Exists(to.display().to_string()).into());
+        }
+
+        std::fs::rename(&source, &target)?;
+        self.undo.push(Undo::Renamed(source, target));
+        Ok(())
+    }
+
+    /// Undo everything in reverse, returning the first path that couldn't
+    /// be put back. Later steps are still undone after one fails.
+    fn rollback(&mut self) -> Result<(), PathBuf> {
+        let mut failed = None;
+        while let Some(step) = self.undo.pop() {
+            let (path, result) = match step {
+                Undo::Created(path) => {
+                    let result = std::fs::remove_file(&path);
+                    (path, result)
+                }
+                Undo::CreatedFolder(path) => {
+                    let result = std::fs::remove_dir(&path);
+                    (path, result)
+                }
+                Undo::Edited(path, original) => {
+                    let result = write(&path, &original);
+                    (path, result)
+                }
+                Undo::Removed(path, contents, permissions) => {
+                    let result = std::fs::write(&path, contents)
+                        .and_then(|()| std::fs::set_permissions(&path, permissions));
+                    (path, result)
+                }
+                Undo::RemovedFolder(path) => {
+                    let result = std::fs::create_dir(&path);
+                    (path, result)
+                }
+                Undo::RemovedLink(path, link) => {
+                    let result = symlink(&link, &path);
+                    (path, result)
+                }
+                Undo::Renamed(from, to) => {
+                    let result = std::fs::rename(&to, &from);
+                    (from, result)
+                }
+            };
+            if result.is_err() && failed.is_none() {
+                failed = Some(path);
+            }
+        }
+        failed.map_or(Ok(()), Err)
+    }
+}
+
+/// Replace the file at `path` by writing a temporary file beside it and
+/// moving that into place, so a failed write never leaves it half written.
+fn write(path: &Path, contents: &str) -> std::io::Result<()> {
+    let folder = path.parent().unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(folder)?;
+    file.write_all(contents.as_bytes())?;
+    // Temporary files are only readable by their owner
+    file.as_file()
+        .set_permissions(std::fs::metadata(path)?.permissions())?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Put the link at `path` back, pointing at `link`.
+#[cfg(unix)]
+fn symlink(link: &Path, path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(link, path)
+}
+
+#[cfg(not(unix))]
+fn symlink(_link: &Path, _path: &Path) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_put_every_file_back() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mod.rs"), "mod a;\n").unwrap();
+
+        let result = run(dir.path(), |transaction| {
+            transaction.create(Path::new("b/c/mod.rs"), "fn c() {}")?;
+            transaction.edit(Path::new("mod.rs"), |source| {
+                Ok(format!("{source}mod b;\n"))
+            })?;
+            transaction.create(Path::new("mod.rs"), "")
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "mod.rs already exists");
+        assert!(!dir.path().join("b").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("mod.rs")).unwrap(),
+            "mod a;\n"
+        );
+    }
+
+    #[test]
+    fn removed_and_renamed_files_come_back() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("b/c")).unwrap();
+        std::fs::write(dir.path().join("b/c/mod.rs"), "fn c() {}").unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+
+        let result = run(dir.path(), |transaction| {
+            transaction.remove(Path::new("b"))?;
+            transaction.rename(Path::new("a.rs"), Path::new("d.rs"))?;
+            transaction.remove(Path::new("b"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "b doesn't exist");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b/c/mod.rs")).unwrap(),
+            "fn c() {}"
+        );
+        assert!(dir.path().join("a.rs").exists());
+        assert!(!dir.path().join("d.rs").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_removed_without_following_them() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("b")).unwrap();
+        std::fs::create_dir_all(dir.path().join("shared")).unwrap();
+        std::fs::write(dir.path().join("shared/mod.rs"), "fn s() {}").unwrap();
+        std::os::unix::fs::symlink("../shared", dir.path().join("b/shared")).unwrap();
+
+        let result = run(dir.path(), |transaction| {
+            transaction.remove(Path::new("b"))?;
+            assert!(dir.path().join("shared/mod.rs").exists());
+            transaction.remove(Path::new("b"))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "b doesn't exist");
+        assert_eq!(
+            std::fs::read_link(dir.path().join("b/shared")).unwrap(),
+            Path::new("../shared")
+        );
+        assert!(dir.path().join("b/shared/mod.rs").exists());
+    }
+
+    #[test]
+    fn successes_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+
+        run(dir.path(), |transaction| {
+            transaction.create(Path::new("a.rs"), "fn a() {}")
+        })
+        .unwrap();
+
+        assert!(dir.path().join("a.rs").exists());
+    }
+}
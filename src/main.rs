@@ -39,8 +39,11 @@ enum Commands {
 }
 
 fn main() -> crate::Result<()> {
-    color_eyre::install()?;
+    api::terminal::install_hooks()?;
+    // After parsing, so a broken config file doesn't get in the way of
+    // --help and --version
     let cli = Cli::parse();
+    api::config::init()?;
 
     if let Some(cmds) = &cli.command {
         match cmds {